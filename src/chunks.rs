@@ -0,0 +1,122 @@
+use alloc::{vec, vec::Vec};
+use core::{iter::FusedIterator, slice};
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::{node::Node, MagicList};
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    pub fn chunks(&self) -> Chunks<'_, T, B, A> {
+        Chunks::over(slice::from_ref(&self.root))
+    }
+
+    pub fn chunks_mut(&mut self) -> ChunksMut<'_, T, B, A> {
+        ChunksMut::over(slice::from_mut(&mut self.root))
+    }
+
+    /// Returns the leaf containing index `i`, along with the position of `i`
+    /// within it.
+    pub fn chunk_at(&self, i: usize) -> (&[T], usize) {
+        assert!(i < self.len(), "out of bounds");
+        let (path, offset) = self.root.path_to(i);
+        (self.root.leaf(&path), offset)
+    }
+}
+
+/// Iterator over the leaves of a `MagicList` as contiguous slices, in order.
+/// Empty leaves are skipped.
+pub struct Chunks<'a, T, const B: usize = 12, A: Allocator = Global> {
+    stack: Vec<slice::Iter<'a, Node<T, B, A>>>,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Chunks<'a, T, B, A> {
+    /// Leaves of `nodes`, which must all be at the same depth.
+    pub(crate) fn over(nodes: &'a [Node<T, B, A>]) -> Self {
+        Self {
+            stack: vec![nodes.iter()],
+        }
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Iterator for Chunks<'a, T, B, A> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(Node::Leaf(x)) => {
+                    if !x.is_empty() {
+                        return Some(x);
+                    }
+                }
+                Some(Node::Tree(x)) => self.stack.push(x.children.iter()),
+            }
+        }
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> FusedIterator for Chunks<'a, T, B, A> {}
+
+/// Mutable counterpart of `Chunks`.
+pub struct ChunksMut<'a, T, const B: usize = 12, A: Allocator = Global> {
+    stack: Vec<slice::IterMut<'a, Node<T, B, A>>>,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> ChunksMut<'a, T, B, A> {
+    pub(crate) fn over(nodes: &'a mut [Node<T, B, A>]) -> Self {
+        Self {
+            stack: vec![nodes.iter_mut()],
+        }
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Iterator for ChunksMut<'a, T, B, A> {
+    type Item = &'a mut [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(Node::Leaf(x)) => {
+                    if !x.is_empty() {
+                        return Some(x);
+                    }
+                }
+                Some(Node::Tree(x)) => self.stack.push(x.children.iter_mut()),
+            }
+        }
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> FusedIterator for ChunksMut<'a, T, B, A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks() {
+        for size in 0..200 {
+            let mut list: MagicList<_> = (0..size).collect();
+            assert!(list.chunks().flatten().copied().eq(0..size));
+            for chunk in list.chunks_mut() {
+                chunk.reverse();
+            }
+            let mut start = 0;
+            for chunk in list.chunks() {
+                assert!(!chunk.is_empty());
+                for (j, &x) in chunk.iter().enumerate() {
+                    assert_eq!(list.chunk_at(start + j), (chunk, j));
+                    assert_eq!(x, start + chunk.len() - 1 - j);
+                }
+                start += chunk.len();
+            }
+            assert_eq!(start, size);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use alloc::{vec, vec::Vec};
+use core::mem::replace;
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::{node::Node, MagicList};
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    pub fn cursor_at(&self, i: usize) -> Cursor<'_, T, B, A> {
+        let mut cursor = Cursor {
+            list: self,
+            path: vec![],
+            leaf: &[],
+            offset: 0,
+            index: 0,
+        };
+        cursor.seek(i);
+        cursor
+    }
+
+    pub fn cursor_mut_at(&mut self, i: usize) -> CursorMut<'_, T, B, A> {
+        let mut cursor = CursorMut {
+            list: self,
+            path: vec![],
+            leaf_len: 0,
+            offset: 0,
+            index: 0,
+        };
+        cursor.seek(i);
+        cursor
+    }
+}
+
+/// A cursor over a `MagicList`, positioned either at an element or at the
+/// "ghost" position at index `len()`.
+///
+/// Moving past either end wraps around through the ghost position, like
+/// `std::collections::linked_list::Cursor`.
+pub struct Cursor<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a MagicList<T, B, A>,
+    path: Vec<usize>,
+    leaf: &'a [T],
+    offset: usize,
+    index: usize,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Cursor<'a, T, B, A> {
+    pub fn index(&self) -> Option<usize> {
+        (self.index < self.list.len()).then_some(self.index)
+    }
+
+    pub fn current(&self) -> Option<&'a T> {
+        self.leaf.get(self.offset)
+    }
+
+    pub fn seek(&mut self, i: usize) {
+        assert!(i <= self.list.len(), "out of bounds");
+        (self.path, self.offset) = self.list.root.path_to(i);
+        self.leaf = self.list.root.leaf(&self.path);
+        self.index = i;
+    }
+
+    pub fn move_next(&mut self) {
+        if self.index == self.list.len() {
+            self.seek(0);
+            return;
+        }
+        self.index += 1;
+        self.offset += 1;
+        if self.offset == self.leaf.len() {
+            if let Some(leaf) = self.list.root.next_leaf(&mut self.path) {
+                self.leaf = leaf;
+                self.offset = 0;
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        if self.index == 0 {
+            self.seek(self.list.len());
+            return;
+        }
+        self.index -= 1;
+        if self.offset == 0 {
+            self.leaf = self.list.root.prev_leaf(&mut self.path).unwrap();
+            self.offset = self.leaf.len();
+        }
+        self.offset -= 1;
+    }
+}
+
+/// A cursor over a `MagicList` that can also edit the list at its position.
+///
+/// Like `Cursor`, it sits either at an element or at the ghost position at
+/// index `len()`.
+pub struct CursorMut<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a mut MagicList<T, B, A>,
+    path: Vec<usize>,
+    leaf_len: usize,
+    offset: usize,
+    index: usize,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> CursorMut<'a, T, B, A> {
+    pub fn index(&self) -> Option<usize> {
+        (self.index < self.list.len()).then_some(self.index)
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.list.root.leaf_mut(&self.path).get_mut(self.offset)
+    }
+
+    pub fn seek(&mut self, i: usize) {
+        assert!(i <= self.list.len(), "out of bounds");
+        (self.path, self.offset) = self.list.root.path_to(i);
+        self.leaf_len = self.list.root.leaf(&self.path).len();
+        self.index = i;
+    }
+
+    pub fn move_next(&mut self) {
+        if self.index == self.list.len() {
+            self.seek(0);
+            return;
+        }
+        self.index += 1;
+        self.offset += 1;
+        if self.offset == self.leaf_len {
+            if let Some(leaf) = self.list.root.next_leaf(&mut self.path) {
+                self.leaf_len = leaf.len();
+                self.offset = 0;
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        if self.index == 0 {
+            self.seek(self.list.len());
+            return;
+        }
+        self.index -= 1;
+        if self.offset == 0 {
+            self.leaf_len = self.list.root.prev_leaf(&mut self.path).unwrap().len();
+            self.offset = self.leaf_len;
+        }
+        self.offset -= 1;
+    }
+
+    /// Inserts `x` before the current position. The cursor stays on the same
+    /// element.
+    pub fn insert_before(&mut self, x: T) {
+        self.leaf_len = self
+            .list
+            .root
+            .insert_at_path(&mut self.path, &mut self.offset, x);
+        self.index += 1;
+        if self.list.root.is_overfull() {
+            // `grow_root` splits the root's entries in half under a new root.
+            let half = self.list.root.children_count() / 2;
+            let entry = self.path.first_mut().unwrap_or(&mut self.offset);
+            let side = usize::from(*entry >= half);
+            *entry -= side * half;
+            self.list.grow_root();
+            self.path.insert(0, side);
+            self.leaf_len = self.list.root.leaf(&self.path).len();
+        }
+    }
+
+    /// Removes the current element, moving the cursor to the next one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.index()?;
+        let (x, leaf_len) = self
+            .list
+            .root
+            .remove_at_path(&mut self.path, &mut self.offset);
+        self.leaf_len = leaf_len;
+        // Collapse roots with a single child, as `make_canon` would.
+        while let Node::Tree(tree) = &mut self.list.root {
+            if tree.children.len() != 1 {
+                break;
+            }
+            self.list.root = tree.children.pop().unwrap();
+            self.path.remove(0);
+        }
+        if self.offset == self.leaf_len && self.index < self.list.len() {
+            self.leaf_len = self.list.root.next_leaf(&mut self.path).unwrap().len();
+            self.offset = 0;
+        }
+        Some(x)
+    }
+
+    /// Splits off everything before the current position and returns it,
+    /// leaving the cursor at index 0.
+    pub fn split_before(&mut self) -> MagicList<T, B, A> {
+        let right = self.list.split_off(self.index);
+        let left = replace(self.list, right);
+        self.seek(0);
+        left
+    }
+
+    /// Inserts `other` after the current element, or at the front of the list
+    /// if the cursor is at the ghost position.
+    pub fn splice_after(&mut self, other: MagicList<T, B, A>) {
+        let at = self.index().map_or(0, |i| i + 1);
+        let right = self.list.split_off(at);
+        self.list.join(other);
+        self.list.join(right);
+        let i = if at == 0 { self.list.len() } else { self.index };
+        self.seek(i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+
+    #[test]
+    fn walk() {
+        for size in 0..200 {
+            let list: MagicList<_> = (0..size).collect();
+            let mut cursor = list.cursor_at(0);
+            for i in 0..size {
+                assert_eq!(cursor.index(), Some(i));
+                assert_eq!(cursor.current(), Some(&i));
+                cursor.move_next();
+            }
+            assert_eq!(cursor.index(), None);
+            assert_eq!(cursor.current(), None);
+            for i in (0..size).rev() {
+                cursor.move_prev();
+                assert_eq!(cursor.current(), Some(&i));
+            }
+            cursor.move_prev();
+            assert_eq!(cursor.current(), None);
+            cursor.move_prev();
+            assert_eq!(cursor.current(), size.checked_sub(1).as_ref());
+        }
+    }
+
+    #[test]
+    fn edits() {
+        check_edits::<2>();
+        check_edits::<3>();
+        check_edits::<12>();
+    }
+
+    fn check_edits<const B: usize>() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        let mut list = MagicList::<_, B>::default();
+        let mut v = vec![];
+        let mut cursor = list.cursor_mut_at(0);
+        let mut i = 0;
+        for n in 0..2000 {
+            match rng.gen_range(0..6) {
+                0 | 1 => {
+                    cursor.insert_before(n);
+                    v.insert(i, n);
+                    i += 1;
+                }
+                2 => {
+                    if i < v.len() {
+                        assert_eq!(cursor.remove_current(), Some(v.remove(i)));
+                    } else {
+                        assert_eq!(cursor.remove_current(), None);
+                    }
+                }
+                3 => {
+                    cursor.move_next();
+                    i = if i == v.len() { 0 } else { i + 1 };
+                }
+                4 => {
+                    cursor.move_prev();
+                    i = if i == 0 { v.len() } else { i - 1 };
+                }
+                _ => {
+                    i = rng.gen_range(0..=v.len());
+                    cursor.seek(i);
+                }
+            }
+            assert_eq!(cursor.current().copied(), v.get(i).copied());
+            assert_eq!(cursor.index(), (i < v.len()).then_some(i));
+        }
+        list.check_invariants().unwrap();
+        assert!(list.iter().eq(&v));
+
+        let mut cursor = list.cursor_mut_at(i);
+        while !v.is_empty() {
+            if i == v.len() || rng.gen_range(0..4) == 0 {
+                i = rng.gen_range(0..v.len());
+                cursor.seek(i);
+            }
+            assert_eq!(cursor.remove_current(), Some(v.remove(i)));
+            assert_eq!(cursor.current().copied(), v.get(i).copied());
+        }
+        list.check_invariants().unwrap();
+        assert!(list.into_iter().eq(v));
+    }
+
+    #[test]
+    fn insert_splits_leaf_root() {
+        for at in 0..=4 {
+            let mut list: MagicList<_, 2> = (0..4).collect();
+            let mut cursor = list.cursor_mut_at(at);
+            cursor.insert_before(100);
+            let mut v: Vec<_> = (0..4).collect();
+            v.insert(at, 100);
+            for i in at + 1..=v.len() {
+                assert_eq!(cursor.index(), (i < v.len()).then_some(i));
+                assert_eq!(cursor.current().copied(), v.get(i).copied());
+                cursor.move_next();
+            }
+            cursor.seek(at + 1);
+            while cursor.index().is_some() {
+                assert_eq!(cursor.remove_current(), Some(v.remove(at + 1)));
+            }
+            assert!(list.into_iter().eq(v));
+        }
+    }
+
+    #[test]
+    fn split_and_splice() {
+        let mut list: MagicList<_> = (0..100).collect();
+        let mut cursor = list.cursor_mut_at(40);
+        let left = cursor.split_before();
+        assert_eq!(cursor.current(), Some(&mut 40));
+        cursor.seek(59);
+        cursor.splice_after(left);
+        assert_eq!(cursor.current(), Some(&mut 99));
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 0));
+        assert!(list.into_iter().eq((40..100).chain(0..40)));
+    }
+}
@@ -0,0 +1,107 @@
+use alloc::string::String;
+use core::fmt::{self, Debug, Display, Write};
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::{node::Node, MagicList};
+
+impl<T: Debug, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    /// Returns a value whose `Display` output shows the internal tree, one
+    /// node per line, indented by depth.
+    pub fn debug_tree(&self) -> DebugTree<'_, T, B, A> {
+        DebugTree { list: self }
+    }
+
+    /// Renders the internal tree as a Graphviz `digraph`.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n    node [shape=box];\n");
+        let mut next_id = 0;
+        self.root.write_dot(&mut out, &mut next_id).unwrap();
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Indented dump of the internal structure of a `MagicList`, created by
+/// [`MagicList::debug_tree`].
+pub struct DebugTree<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a MagicList<T, B, A>,
+}
+
+impl<'a, T: Debug, const B: usize, A: Allocator + Clone> Display for DebugTree<'a, T, B, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.list.root.write_tree(f, 0)
+    }
+}
+
+impl<T: Debug, const B: usize, A: Allocator + Clone> Node<T, B, A> {
+    fn write_tree(&self, f: &mut impl Write, level: usize) -> fmt::Result {
+        write!(f, "{:1$}", "", level * 2)?;
+        match self {
+            Node::Leaf(x) => writeln!(f, "Leaf {:?}", &x[..]),
+            Node::Tree(x) => {
+                writeln!(
+                    f,
+                    "Tree {{ total_len: {}, children: {} }}",
+                    x.total_len,
+                    x.children.len()
+                )?;
+                for child in x.children.iter() {
+                    child.write_tree(f, level + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes this node and its subtree, returning the id of this node.
+    fn write_dot(&self, out: &mut String, next_id: &mut usize) -> Result<usize, fmt::Error> {
+        let id = *next_id;
+        *next_id += 1;
+        match self {
+            Node::Leaf(x) => {
+                let mut label = String::new();
+                write!(label, "{:?}", &x[..])?;
+                let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+                writeln!(out, "    n{id} [label=\"{label}\"];")?;
+            }
+            Node::Tree(x) => {
+                writeln!(out, "    n{id} [label=\"total_len: {}\"];", x.total_len)?;
+                for child in x.children.iter() {
+                    let child_id = child.write_dot(out, next_id)?;
+                    writeln!(out, "    n{id} -> n{child_id};")?;
+                }
+            }
+        }
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_tree() {
+        let mut list: MagicList<_, 2> = (0..6).collect();
+        list.shrink_to_fit();
+        assert_eq!(
+            list.debug_tree().to_string(),
+            "Tree { total_len: 6, children: 2 }\n  Leaf [0, 1, 2]\n  Leaf [3, 4, 5]\n"
+        );
+        assert_eq!(
+            list.to_dot(),
+            "digraph {
+    node [shape=box];
+    n0 [label=\"total_len: 6\"];
+    n1 [label=\"[0, 1, 2]\"];
+    n0 -> n1;
+    n2 [label=\"[3, 4, 5]\"];
+    n0 -> n2;
+}
+"
+        );
+        let quoted: MagicList<_> = ["a\"b"].into_iter().collect();
+        assert!(quoted.to_dot().contains(r#"[label="[\"a\\\"b\"]"]"#));
+    }
+}
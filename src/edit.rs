@@ -0,0 +1,277 @@
+use core::{iter::FusedIterator, mem::replace, ops::RangeBounds};
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::{
+    node::{Builder, IntoElements},
+    slice::to_range,
+    MagicList,
+};
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    /// Keeps the first `len` elements and drops the rest. Does nothing if the
+    /// list is already shorter.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            drop(self.split_off(len));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        drop(self.root.take());
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.retain_mut(|x| f(x));
+    }
+
+    /// Keeps only the elements for which `f` returns true, in one pass that
+    /// packs the survivors into fresh leaves.
+    ///
+    /// If `f` panics, the list keeps every element it had not yet removed,
+    /// like `Vec::retain_mut`.
+    pub fn retain_mut(&mut self, mut f: impl FnMut(&mut T) -> bool) {
+        let mut rebuild = self.rebuild();
+        for x in rebuild.items.by_ref() {
+            rebuild.kept.push(x);
+            if !f(rebuild.kept.last_mut().unwrap()) {
+                rebuild.kept.pop();
+            }
+        }
+    }
+
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
+    pub fn dedup_by_key<K: PartialEq>(&mut self, mut key: impl FnMut(&mut T) -> K) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes consecutive elements for which `same_bucket(current, previous)`
+    /// returns true, like `Vec::dedup_by`. Runs in one pass, like
+    /// `retain_mut`, and likewise keeps the remaining elements if
+    /// `same_bucket` panics.
+    pub fn dedup_by(&mut self, mut same_bucket: impl FnMut(&mut T, &mut T) -> bool) {
+        let mut rebuild = self.rebuild();
+        for x in rebuild.items.by_ref() {
+            rebuild.kept.push(x);
+            if let Some((prev, x)) = rebuild.kept.last_two_mut() {
+                if same_bucket(x, prev) {
+                    rebuild.kept.pop();
+                }
+            }
+        }
+    }
+
+    /// Removes and yields the elements in `range` for which `pred` returns
+    /// true, visiting them leaf by leaf. The kept elements are packed into new
+    /// leaves as they are visited and joined back when the iterator is
+    /// dropped; elements it never reached stay in the list.
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        pred: F,
+    ) -> ExtractIf<'_, T, F, B, A> {
+        let (start, end) = to_range(range, self.len());
+        let right = self.split_off(end);
+        let items = self.split_off(start).root.into_elements();
+        ExtractIf {
+            kept: Builder::new(self.allocator().clone()),
+            list: self,
+            right,
+            items,
+            pred,
+        }
+    }
+
+    /// Moves the elements out of the tree. The caller pushes the ones it keeps
+    /// from `items` into `kept`, which packs them into new leaves; the tree is
+    /// finished from both when the guard is dropped, including during a panic.
+    fn rebuild(&mut self) -> Rebuild<'_, T, B, A> {
+        let items = self.root.take().into_elements();
+        Rebuild {
+            kept: Builder::new(self.allocator().clone()),
+            list: self,
+            items,
+        }
+    }
+}
+
+struct Rebuild<'a, T, const B: usize, A: Allocator + Clone> {
+    list: &'a mut MagicList<T, B, A>,
+    kept: Builder<T, B, A>,
+    items: IntoElements<T, B, A>,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Drop for Rebuild<'a, T, B, A> {
+    fn drop(&mut self) {
+        self.items.by_ref().for_each(|x| self.kept.push(x));
+        self.list.root = self.kept.finish();
+    }
+}
+
+/// Iterator returned by [`MagicList::extract_if`].
+pub struct ExtractIf<'a, T, F, const B: usize = 12, A: Allocator + Clone = Global> {
+    list: &'a mut MagicList<T, B, A>,
+    right: MagicList<T, B, A>,
+    items: IntoElements<T, B, A>,
+    kept: Builder<T, B, A>,
+    pred: F,
+}
+
+impl<'a, T, F: FnMut(&mut T) -> bool, const B: usize, A: Allocator + Clone> Iterator
+    for ExtractIf<'a, T, F, B, A>
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        for x in self.items.by_ref() {
+            self.kept.push(x);
+            if (self.pred)(self.kept.last_mut().unwrap()) {
+                return self.kept.pop();
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.items.len()))
+    }
+}
+
+impl<'a, T, F: FnMut(&mut T) -> bool, const B: usize, A: Allocator + Clone> FusedIterator
+    for ExtractIf<'a, T, F, B, A>
+{
+}
+
+impl<'a, T, F, const B: usize, A: Allocator + Clone> Drop for ExtractIf<'a, T, F, B, A> {
+    fn drop(&mut self) {
+        let alloc = self.list.allocator().clone();
+        self.items.by_ref().for_each(|x| self.kept.push(x));
+        let root = self.kept.finish();
+        self.list.join(MagicList { root });
+        let right = replace(&mut self.right, MagicList::new_in(alloc));
+        self.list.join(right);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+
+    #[test]
+    fn matches_vec() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        for n in [0, 1, 5, 50, 2000] {
+            let v: Vec<u32> = (0..n).map(|_| rng.gen_range(0..4)).collect();
+            let list: MagicList<_, 3> = v.iter().copied().collect();
+
+            let (mut a, mut b) = (list.clone(), v.clone());
+            a.retain(|&x| x != 0);
+            b.retain(|&x| x != 0);
+            assert_eq!(a, b);
+            a.check_invariants().unwrap();
+
+            let (mut a, mut b) = (list.clone(), v.clone());
+            a.dedup();
+            b.dedup();
+            assert_eq!(a, b);
+            a.check_invariants().unwrap();
+
+            let (mut a, mut b) = (list.clone(), v.clone());
+            a.dedup_by_key(|x| *x / 2);
+            b.dedup_by_key(|x| *x / 2);
+            assert_eq!(a, b);
+
+            let (mut a, mut b) = (list.clone(), v.clone());
+            a.truncate(n as usize / 3);
+            b.truncate(n as usize / 3);
+            assert_eq!(a, b);
+            a.check_invariants().unwrap();
+            a.truncate(n as usize);
+            assert_eq!(a, b);
+            a.clear();
+            assert!(a.is_empty());
+        }
+    }
+
+    #[test]
+    fn panic_keeps_elements() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut list: MagicList<_, 2> = (0..100).collect();
+        let mut seen = 0;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.retain(|&x| {
+                seen += 1;
+                assert!(seen <= 50);
+                x % 2 == 0
+            })
+        }));
+        assert!(result.is_err());
+        assert!(list.iter().copied().eq((0..50).step_by(2).chain(50..100)));
+        list.check_invariants().unwrap();
+
+        let mut list: MagicList<_, 2> = [0, 0, 1, 1, 2, 2, 3, 3].into_iter().collect();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.dedup_by(|a, b| {
+                assert!(*a < 2);
+                a == b
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(list, [0, 1, 2, 2, 3, 3]);
+        list.check_invariants().unwrap();
+
+        let mut list: MagicList<_, 2> = (0..100).collect();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.extract_if(20..80, |&mut x| {
+                assert!(x < 60);
+                x % 2 == 0
+            })
+            .for_each(drop)
+        }));
+        assert!(result.is_err());
+        assert!(list
+            .iter()
+            .copied()
+            .eq((0..20).chain((21..60).step_by(2)).chain(60..100)));
+        list.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn extract_if() {
+        let mut rng = ChaChaRng::seed_from_u64(1);
+        for n in [0, 10, 3000] {
+            let v: Vec<u32> = (0..n).map(|_| rng.gen_range(0..10)).collect();
+            let list: MagicList<_, 2> = v.iter().copied().collect();
+            let range = n as usize / 4..n as usize / 2;
+
+            let (mut a, mut b) = (list.clone(), v.clone());
+            let taken: Vec<_> = a.extract_if(range.clone(), |x| *x == 0).collect();
+            assert_eq!(
+                taken,
+                b.extract_if(range.clone(), |x| *x == 0).collect::<Vec<_>>()
+            );
+            assert_eq!(a, b);
+            a.check_invariants().unwrap();
+
+            // Stopping early keeps everything not yet visited.
+            let (mut a, mut b) = (list.clone(), v.clone());
+            let taken: Vec<_> = a.extract_if(.., |x| *x < 5).take(3).collect();
+            assert_eq!(
+                taken,
+                b.extract_if(.., |x| *x < 5).take(3).collect::<Vec<_>>()
+            );
+            assert_eq!(a, b);
+            a.check_invariants().unwrap();
+        }
+    }
+}
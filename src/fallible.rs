@@ -0,0 +1,435 @@
+//! Fallible counterparts of the growing operations.
+//!
+//! The infallible versions insert first and rebalance afterwards, which can
+//! allocate at any point along the way. These instead split full nodes on the
+//! way down (which never changes the contents) and acquire every allocation
+//! they need before moving any elements, so an error leaves the list as it
+//! was.
+
+use allocator_api2::{alloc::Allocator, collections::TryReserveError, vec::Vec};
+
+use crate::{
+    node::{Node, Tree},
+    MagicList,
+};
+
+type RootVecs<T, const B: usize, A> = (Vec<usize, A>, Vec<Node<T, B, A>, A>);
+
+impl<T, const B: usize, A: Allocator + Clone> Node<T, B, A> {
+    fn is_full(&self) -> bool {
+        self.children_count() >= 2 * B
+    }
+
+    fn empty_like(&self) -> Self {
+        match self {
+            Node::Leaf(x) => Node::Leaf(Vec::new_in(x.allocator().clone())),
+            Node::Tree(x) => Node::Tree(Tree {
+                total_len: 0,
+                lens: Vec::new_in(x.lens.allocator().clone()),
+                children: Vec::new_in(x.children.allocator().clone()),
+            }),
+        }
+    }
+
+    fn try_reserve_entries(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        match self {
+            Node::Leaf(x) => x.try_reserve(additional),
+            Node::Tree(x) => {
+                x.lens.try_reserve(additional)?;
+                x.children.try_reserve(additional)
+            }
+        }
+    }
+
+    /// Moves the last `n` entries of `self` to the front of `to`, which must
+    /// already have room for them.
+    fn move_back_to_front(&mut self, to: &mut Self, n: usize) {
+        match (self, to) {
+            (Node::Leaf(a), Node::Leaf(b)) => {
+                let at = a.len() - n;
+                b.splice(0..0, a.drain(at..));
+            }
+            (Node::Tree(a), Node::Tree(b)) => {
+                let at = a.children.len() - n;
+                let moved = a.lens[at..].iter().sum::<usize>();
+                a.total_len -= moved;
+                b.total_len += moved;
+                b.lens.splice(0..0, a.lens.drain(at..));
+                b.children.splice(0..0, a.children.drain(at..));
+            }
+            _ => unreachable!("must be same type"),
+        }
+    }
+
+    /// Moves the first `n` entries of `self` to the back of `to`, which must
+    /// already have room for them.
+    fn move_front_to_back(&mut self, to: &mut Self, n: usize) {
+        match (self, to) {
+            (Node::Leaf(a), Node::Leaf(b)) => b.extend(a.drain(..n)),
+            (Node::Tree(a), Node::Tree(b)) => {
+                let moved = a.lens[..n].iter().sum::<usize>();
+                a.total_len -= moved;
+                b.total_len += moved;
+                b.lens.extend(a.lens.drain(..n));
+                b.children.extend(a.children.drain(..n));
+            }
+            _ => unreachable!("must be same type"),
+        }
+    }
+
+    fn try_split_off_half(&mut self) -> Result<Self, TryReserveError> {
+        let n = self.children_count() - self.children_count() / 2;
+        let mut right = self.empty_like();
+        right.try_reserve_entries(n)?;
+        self.move_back_to_front(&mut right, n);
+        Ok(right)
+    }
+
+    fn try_reserve_insert(&mut self, i: usize) -> Result<(), TryReserveError> {
+        match self {
+            Node::Leaf(x) => x.try_reserve(1),
+            Node::Tree(tree) => {
+                let (child_i, _) = tree.find_child(i);
+                if tree.children[child_i].is_full() {
+                    tree.try_split_child(child_i)?;
+                }
+                let (child_i, i) = tree.find_child(i);
+                tree.children[child_i].try_reserve_insert(i)
+            }
+        }
+    }
+
+    /// Inserts along the path prepared by `try_reserve_insert`, without
+    /// rebalancing or allocating.
+    fn insert_reserved(&mut self, i: usize, x: T) {
+        match self {
+            Node::Leaf(v) => v.insert(i, x),
+            Node::Tree(tree) => {
+                let (child_i, i) = tree.find_child(i);
+                tree.total_len += 1;
+                tree.lens[child_i] += 1;
+                tree.children[child_i].insert_reserved(i, x);
+            }
+        }
+    }
+
+    /// Attaches `other`, whose depth is `other_depth`, to the right edge of
+    /// `self`, which is deeper and not full. `other` is only taken once
+    /// nothing else can fail.
+    fn try_attach_right(
+        &mut self,
+        other: &mut Self,
+        depth: usize,
+        other_depth: usize,
+    ) -> Result<(), TryReserveError> {
+        let Node::Tree(tree) = self else {
+            unreachable!()
+        };
+        let other_len = other.len();
+        let last = tree.children.len() - 1;
+        if depth - 1 > other_depth {
+            if tree.children[last].is_full() {
+                tree.try_split_child(last)?;
+            }
+            let last = tree.children.len() - 1;
+            tree.children[last].try_attach_right(other, depth - 1, other_depth)?;
+            tree.lens[last] += other_len;
+        } else {
+            let count = tree.children[last].children_count();
+            let other_count = other.children_count();
+            if other_count >= B {
+                tree.lens.try_reserve(1)?;
+                tree.children.try_reserve(1)?;
+                tree.lens.push(other_len);
+                tree.children.push(other.take());
+            } else if count + other_count <= 2 * B {
+                tree.children[last].try_reserve_entries(other_count)?;
+                tree.children[last].extend_equal_level(other.take());
+                tree.update_len(last);
+            } else {
+                let n = count - (count + other_count) / 2;
+                tree.lens.try_reserve(1)?;
+                tree.children.try_reserve(1)?;
+                other.try_reserve_entries(n)?;
+                tree.children[last].move_back_to_front(other, n);
+                tree.update_len(last);
+                tree.lens.push(other.len());
+                tree.children.push(other.take());
+            }
+        }
+        tree.total_len += other_len;
+        Ok(())
+    }
+
+    /// Mirror image of `try_attach_right`.
+    fn try_attach_left(
+        &mut self,
+        other: &mut Self,
+        depth: usize,
+        other_depth: usize,
+    ) -> Result<(), TryReserveError> {
+        let Node::Tree(tree) = self else {
+            unreachable!()
+        };
+        let other_len = other.len();
+        if depth - 1 > other_depth {
+            if tree.children[0].is_full() {
+                tree.try_split_child(0)?;
+            }
+            tree.children[0].try_attach_left(other, depth - 1, other_depth)?;
+            tree.lens[0] += other_len;
+        } else {
+            let count = tree.children[0].children_count();
+            let other_count = other.children_count();
+            if other_count >= B {
+                tree.lens.try_reserve(1)?;
+                tree.children.try_reserve(1)?;
+                tree.lens.insert(0, other_len);
+                tree.children.insert(0, other.take());
+            } else if count + other_count <= 2 * B {
+                tree.children[0].try_reserve_entries(other_count)?;
+                tree.children[0].prepend_equal_level(other.take());
+                tree.update_len(0);
+            } else {
+                let n = count - (count + other_count) / 2;
+                tree.lens.try_reserve(1)?;
+                tree.children.try_reserve(1)?;
+                other.try_reserve_entries(n)?;
+                tree.children[0].move_front_to_back(other, n);
+                tree.update_len(0);
+                tree.lens.insert(0, other.len());
+                tree.children.insert(0, other.take());
+            }
+        }
+        tree.total_len += other_len;
+        Ok(())
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> Tree<T, B, A> {
+    fn try_split_child(&mut self, i: usize) -> Result<(), TryReserveError> {
+        self.lens.try_reserve(1)?;
+        self.children.try_reserve(1)?;
+        let new_child = self.children[i].try_split_off_half()?;
+        self.lens.insert(i + 1, new_child.len());
+        self.children.insert(i + 1, new_child);
+        self.update_len(i);
+        Ok(())
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    fn try_reserve_root(&self) -> Result<RootVecs<T, B, A>, TryReserveError> {
+        let mut lens = Vec::new_in(self.allocator().clone());
+        let mut children = Vec::new_in(self.allocator().clone());
+        lens.try_reserve_exact(2)?;
+        children.try_reserve_exact(2)?;
+        Ok((lens, children))
+    }
+
+    /// Puts the current root and `right` under a new root, using vectors from
+    /// `try_reserve_root`.
+    fn set_root(&mut self, (mut lens, mut children): RootVecs<T, B, A>, right: Node<T, B, A>) {
+        let left = self.root.take();
+        lens.extend([left.len(), right.len()]);
+        children.extend([left, right]);
+        self.root = Node::Tree(Tree {
+            total_len: lens.iter().sum(),
+            lens,
+            children,
+        });
+    }
+
+    fn try_split_root(&mut self) -> Result<(), TryReserveError> {
+        let n = self.root.children_count() - self.root.children_count() / 2;
+        let mut right = self.root.empty_like();
+        right.try_reserve_entries(n)?;
+        let root = self.try_reserve_root()?;
+        self.root.move_back_to_front(&mut right, n);
+        self.set_root(root, right);
+        Ok(())
+    }
+
+    fn try_append(&mut self, mut other: Self) -> Result<(), TryReserveError> {
+        if other.is_empty() {
+            return Ok(());
+        }
+        if self.is_empty() {
+            *self = other;
+            return Ok(());
+        }
+        let depth = self.root.depth();
+        let other_depth = other.root.depth();
+        match depth.cmp(&other_depth) {
+            core::cmp::Ordering::Greater => {
+                if self.root.is_full() {
+                    self.try_split_root()?;
+                }
+                let depth = self.root.depth();
+                self.root
+                    .try_attach_right(&mut other.root, depth, other_depth)
+            }
+            core::cmp::Ordering::Less => {
+                if other.root.is_full() {
+                    other.try_split_root()?;
+                }
+                let other_depth = other.root.depth();
+                other
+                    .root
+                    .try_attach_left(&mut self.root, other_depth, depth)?;
+                *self = other;
+                Ok(())
+            }
+            core::cmp::Ordering::Equal => {
+                let count = self.root.children_count();
+                let other_count = other.root.children_count();
+                if count + other_count <= 2 * B {
+                    self.root.try_reserve_entries(other_count)?;
+                    self.root.extend_equal_level(other.root);
+                    return Ok(());
+                }
+                let half = (count + other_count) / 2;
+                if count < B {
+                    self.root.try_reserve_entries(half - count)?;
+                } else if other_count < B {
+                    other.root.try_reserve_entries(count - half)?;
+                }
+                let root = self.try_reserve_root()?;
+                if count < B {
+                    other.root.move_front_to_back(&mut self.root, half - count);
+                } else if other_count < B {
+                    self.root.move_back_to_front(&mut other.root, count - half);
+                }
+                self.set_root(root, other.root);
+                Ok(())
+            }
+        }
+    }
+
+    /// Like `insert`, but returns an error instead of aborting if an
+    /// allocation fails. The list is left unchanged on error.
+    pub fn try_insert(&mut self, i: usize, x: T) -> Result<(), TryReserveError> {
+        assert!(i <= self.len(), "out of bounds");
+        if self.root.is_full() {
+            self.try_split_root()?;
+        }
+        self.root.try_reserve_insert(i)?;
+        self.root.insert_reserved(i, x);
+        Ok(())
+    }
+
+    /// Like `push`, but returns an error instead of aborting if an allocation
+    /// fails. The list is left unchanged on error.
+    pub fn try_push(&mut self, x: T) -> Result<(), TryReserveError> {
+        self.try_insert(self.len(), x)
+    }
+
+    /// Appends every element of `iter`, or none of them if an allocation
+    /// fails.
+    pub fn try_extend<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        let mut other = Self::new_in(self.allocator().clone());
+        for x in iter {
+            other.try_push(x)?;
+        }
+        self.try_append(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{alloc::Layout, cell::Cell, ptr::NonNull};
+
+    use allocator_api2::alloc::{AllocError, Global};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+
+    /// Fails every allocation once `left` runs out.
+    struct Budget {
+        left: Cell<usize>,
+    }
+
+    unsafe impl Allocator for &Budget {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            if self.left.get() == 0 {
+                return Err(AllocError);
+            }
+            self.left.set(self.left.get() - 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    fn build(budget: &Budget, range: std::ops::Range<usize>) -> MagicList<usize, 12, &Budget> {
+        budget.left.set(usize::MAX);
+        let mut list = MagicList::new_in(budget);
+        for x in range {
+            list.push(x);
+        }
+        list
+    }
+
+    #[test]
+    fn insert_until_success() {
+        let budget = Budget {
+            left: Cell::new(usize::MAX),
+        };
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        for size in [0, 1, 23, 24, 25, 300, 1500] {
+            let mut list = build(&budget, 0..size);
+            let mut v: std::vec::Vec<_> = (0..size).collect();
+            for n in 0..50 {
+                let i = rng.gen_range(0..=v.len());
+                for left in 0.. {
+                    budget.left.set(left);
+                    let result = if n % 2 == 0 {
+                        list.try_insert(i, usize::MAX - n)
+                    } else {
+                        list.try_push(usize::MAX - n)
+                    };
+                    if result.is_ok() {
+                        break;
+                    }
+                    assert!(list.iter().eq(&v));
+                }
+                if n % 2 == 0 {
+                    v.insert(i, usize::MAX - n);
+                } else {
+                    v.push(usize::MAX - n);
+                }
+                assert!(list.iter().eq(&v));
+            }
+        }
+    }
+
+    #[test]
+    fn extend_until_success() {
+        let budget = Budget {
+            left: Cell::new(usize::MAX),
+        };
+        let sizes = [0, 1, 5, 23, 24, 25, 300, 1500];
+        for size in sizes {
+            for other_size in sizes {
+                let mut list = build(&budget, 0..size);
+                for left in 0.. {
+                    budget.left.set(left);
+                    if list.try_extend(size..size + other_size).is_ok() {
+                        break;
+                    }
+                    assert!(list.iter().copied().eq(0..size));
+                }
+                assert!(list.iter().copied().eq(0..size + other_size));
+                budget.left.set(usize::MAX);
+                list.push(usize::MAX);
+                assert_eq!(list.pop(), usize::MAX);
+            }
+        }
+    }
+}
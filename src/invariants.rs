@@ -0,0 +1,192 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use allocator_api2::alloc::Allocator;
+
+use crate::{node::Node, MagicList};
+
+/// A broken structural invariant, found by [`MagicList::check_invariants`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantError {
+    /// Child indices leading from the root to the offending node.
+    pub path: Vec<usize>,
+    pub kind: InvariantErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantErrorKind {
+    /// A leaf sits at a different depth than the leftmost leaf.
+    UnequalDepth,
+    /// A cached length disagrees with the number of elements below it.
+    LenMismatch,
+    /// A non-root node has fewer than `B` children.
+    Underfull,
+    /// A node has more than `2 * B` children.
+    Overfull,
+    /// The root is an inner node with fewer than two children.
+    NonCanonicalRoot,
+}
+
+impl Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            InvariantErrorKind::UnequalDepth => "leaf at the wrong depth",
+            InvariantErrorKind::LenMismatch => "cached length out of sync",
+            InvariantErrorKind::Underfull => "underfull node",
+            InvariantErrorKind::Overfull => "overfull node",
+            InvariantErrorKind::NonCanonicalRoot => "non-canonical root",
+        };
+        write!(f, "{what} at path {:?}", self.path)
+    }
+}
+
+impl core::error::Error for InvariantError {}
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    /// Walks the whole tree and reports the first broken invariant, if any.
+    /// Takes O(n) time; meant for tests and fuzzing.
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        let mut path = Vec::new();
+        if let Node::Tree(x) = &self.root {
+            if x.children.len() < 2 {
+                return Err(InvariantError {
+                    path,
+                    kind: InvariantErrorKind::NonCanonicalRoot,
+                });
+            }
+        }
+        let mut depth = 0;
+        let mut node = &self.root;
+        while let Node::Tree(x) = node {
+            depth += 1;
+            node = &x.children[0];
+        }
+        self.root.check(&mut path, depth).map(|_| ())
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> Node<T, B, A> {
+    /// Checks the subtree at `path`, which should have `depth` levels below
+    /// it, and returns its real length.
+    fn check(&self, path: &mut Vec<usize>, depth: usize) -> Result<usize, InvariantError> {
+        let fail = |path: &Vec<usize>, kind| {
+            Err(InvariantError {
+                path: path.clone(),
+                kind,
+            })
+        };
+        if self.is_overfull() {
+            return fail(path, InvariantErrorKind::Overfull);
+        }
+        if !path.is_empty() && self.is_underfull() {
+            return fail(path, InvariantErrorKind::Underfull);
+        }
+        match self {
+            Node::Leaf(x) if depth == 0 => Ok(x.len()),
+            Node::Tree(x) if depth > 0 => {
+                if x.lens.len() != x.children.len() {
+                    return fail(path, InvariantErrorKind::LenMismatch);
+                }
+                let mut total = 0;
+                for (i, (child, &len)) in x.children.iter().zip(&x.lens).enumerate() {
+                    path.push(i);
+                    let real_len = child.check(path, depth - 1)?;
+                    if real_len != len {
+                        return fail(path, InvariantErrorKind::LenMismatch);
+                    }
+                    path.pop();
+                    total += real_len;
+                }
+                if total != x.total_len {
+                    return fail(path, InvariantErrorKind::LenMismatch);
+                }
+                Ok(total)
+            }
+            _ => fail(path, InvariantErrorKind::UnequalDepth),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+
+    fn mixed_edits<const B: usize>() {
+        let mut rng = ChaChaRng::seed_from_u64(B as u64);
+        let mut list = MagicList::<_, B>::default();
+        let mut v = vec![];
+        for n in 0..800 {
+            let i = rng.gen_range(0..=v.len());
+            match rng.gen_range(0..10) {
+                0 if i < v.len() => assert_eq!(list.remove(i), v.remove(i)),
+                1 => {
+                    let mut right = list.split_off(i);
+                    list.check_invariants().unwrap();
+                    right.check_invariants().unwrap();
+                    list.append(&mut right);
+                }
+                2 => {
+                    let mut other: MagicList<_, B> = (0..rng.gen_range(0..40)).collect();
+                    v.splice(i..i, other.iter().copied());
+                    let mut right = list.split_off(i);
+                    list.append(&mut other);
+                    list.append(&mut right);
+                }
+                3 => {
+                    list.try_insert(i, n).unwrap();
+                    v.insert(i, n);
+                }
+                4 => {
+                    let mut cursor = list.cursor_mut_at(i);
+                    if rng.gen_bool(0.5) {
+                        cursor.insert_before(n);
+                        v.insert(i, n);
+                    } else if cursor.remove_current().is_some() {
+                        v.remove(i);
+                    }
+                }
+                5 if !v.is_empty() => assert_eq!(list.pop(), v.pop().unwrap()),
+                6 => {
+                    let n = rng.gen_range(0..40);
+                    list.try_extend(0..n).unwrap();
+                    v.extend(0..n);
+                }
+                7 if rng.gen_bool(0.1) => list.shrink_to_fit(),
+                _ => {
+                    list.insert(i, n);
+                    v.insert(i, n);
+                }
+            }
+            list.check_invariants().unwrap();
+        }
+        assert!(list.iter().eq(v.iter()));
+    }
+
+    #[test]
+    fn holds_after_every_edit() {
+        mixed_edits::<2>();
+        mixed_edits::<3>();
+        mixed_edits::<12>();
+    }
+
+    #[test]
+    fn reports_path() {
+        let mut list: MagicList<_, 2> = (0..100).collect();
+        list.check_invariants().unwrap();
+        let Node::Tree(root) = &mut list.root else {
+            unreachable!()
+        };
+        let Node::Tree(child) = &mut root.children[1] else {
+            unreachable!()
+        };
+        child.lens[2] += 1;
+        let err = list.check_invariants().unwrap_err();
+        assert_eq!(err.path, [1, 2]);
+        assert_eq!(err.kind, InvariantErrorKind::LenMismatch);
+        assert_eq!(err.to_string(), "cached length out of sync at path [1, 2]");
+    }
+}
@@ -1,53 +1,54 @@
-use std::iter::FusedIterator;
+use core::iter::FusedIterator;
 
-use crate::MagicList;
+use allocator_api2::alloc::{Allocator, Global};
 
-impl<T> IntoIterator for MagicList<T> {
+use crate::{
+    node::{IntoElements, Node},
+    MagicList,
+};
+
+impl<T, const B: usize, A: Allocator + Clone> IntoIterator for MagicList<T, B, A> {
     type Item = T;
 
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, B, A>;
 
     fn into_iter(self) -> Self::IntoIter {
-        IntoIter { list: self }
+        IntoIter {
+            elements: self.root.into_elements(),
+        }
     }
 }
 
-pub struct IntoIter<T> {
-    list: MagicList<T>,
+/// Frees each leaf as soon as the iterator has moved past it. Draining a
+/// whole list takes O(n).
+pub struct IntoIter<T, const B: usize = 12, A: Allocator = Global> {
+    elements: IntoElements<T, B, A>,
 }
 
-impl<T> Iterator for IntoIter<T> {
+impl<T, const B: usize, A: Allocator + Clone> Iterator for IntoIter<T, B, A> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.list.is_empty() {
-            None
-        } else {
-            Some(self.list.remove(0))
-        }
+        self.elements.next()
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.list.len(), Some(self.list.len()))
+        self.elements.size_hint()
     }
 }
 
-impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T, const B: usize, A: Allocator + Clone> ExactSizeIterator for IntoIter<T, B, A> {}
 
-impl<T> DoubleEndedIterator for IntoIter<T> {
+impl<T, const B: usize, A: Allocator + Clone> DoubleEndedIterator for IntoIter<T, B, A> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        if self.list.is_empty() {
-            None
-        } else {
-            Some(self.list.pop())
-        }
+        self.elements.next_back()
     }
 }
 
-impl<T> FusedIterator for IntoIter<T> {}
+impl<T, const B: usize, A: Allocator + Clone> FusedIterator for IntoIter<T, B, A> {}
 
-impl<T> MagicList<T> {
-    pub fn iter(&self) -> Iter<T> {
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    pub fn iter(&self) -> Iter<'_, T, B, A> {
         Iter {
             list: self,
             i: 0,
@@ -56,23 +57,23 @@ impl<T> MagicList<T> {
     }
 }
 
-impl<'a, T> IntoIterator for &'a MagicList<T> {
+impl<'a, T, const B: usize, A: Allocator + Clone> IntoIterator for &'a MagicList<T, B, A> {
     type Item = &'a T;
 
-    type IntoIter = Iter<'a, T>;
+    type IntoIter = Iter<'a, T, B, A>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
     }
 }
 
-pub struct Iter<'a, T> {
-    list: &'a MagicList<T>,
-    i: usize,
-    j: usize,
+pub struct Iter<'a, T, const B: usize = 12, A: Allocator = Global> {
+    pub(crate) list: &'a MagicList<T, B, A>,
+    pub(crate) i: usize,
+    pub(crate) j: usize,
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, const B: usize, A: Allocator + Clone> Iterator for Iter<'a, T, B, A> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -91,7 +92,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
-impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+impl<'a, T, const B: usize, A: Allocator + Clone> DoubleEndedIterator for Iter<'a, T, B, A> {
     fn next_back(&mut self) -> Option<Self::Item> {
         assert!(self.i <= self.j);
         if self.i == self.j {
@@ -104,17 +105,29 @@ impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
     }
 }
 
-impl<'a, T> FusedIterator for Iter<'a, T> {}
+impl<'a, T, const B: usize, A: Allocator + Clone> FusedIterator for Iter<'a, T, B, A> {}
 
-impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T, const B: usize, A: Allocator + Clone> ExactSizeIterator for Iter<'a, T, B, A> {}
 
-impl<T> FromIterator<T> for MagicList<T> {
+impl<T, const B: usize, A: Allocator + Clone + Default> FromIterator<T> for MagicList<T, B, A> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut list = MagicList::default();
-        for x in iter {
-            list.push(x);
+        Self {
+            root: Node::build_from(iter.into_iter(), A::default()),
         }
-        list
+    }
+}
+
+/// Bulk-loads the new elements into a subtree and appends it in one step.
+impl<T, const B: usize, A: Allocator + Clone> Extend<T> for MagicList<T, B, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let root = Node::build_from(iter.into_iter(), self.allocator().clone());
+        self.join(Self { root });
+    }
+}
+
+impl<'a, T: Copy + 'a, const B: usize, A: Allocator + Clone> Extend<&'a T> for MagicList<T, B, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
     }
 }
 
@@ -135,4 +148,28 @@ mod tests {
         assert!(iter.len() == 94);
         assert!(iter.copied().eq(3..97));
     }
+
+    #[test]
+    fn into_iter_both_ends() {
+        for n in [0, 1, 5, 100, 1000] {
+            let list: MagicList<_, 2> = (0..n).collect();
+            let mut iter = list.into_iter();
+            let (mut i, mut j) = (0, n);
+            for k in 0..n {
+                assert_eq!(iter.len(), j - i);
+                if k % 3 == 0 {
+                    j -= 1;
+                    assert_eq!(iter.next_back(), Some(j));
+                } else {
+                    assert_eq!(iter.next(), Some(i));
+                    i += 1;
+                }
+            }
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+        }
+        let mut a: MagicList<_> = (0..50).collect();
+        a.extend(MagicList::<_>::from_iter(50..100));
+        assert!(a.into_iter().rev().eq((0..100).rev()));
+    }
 }
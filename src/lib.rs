@@ -1,50 +1,156 @@
-use node::{Node, Tree};
-use std::{
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use allocator_api2::alloc::{Allocator, Global};
+pub use allocator_api2::collections::TryReserveError;
+use core::{
     fmt::Debug,
-    mem::take,
-    ops::{Index, IndexMut},
+    hash::{Hash, Hasher},
+    ops::{Add, AddAssign, Index, IndexMut},
 };
+use node::{Node, Tree};
 
-pub(crate) const B: usize = 12;
+#[macro_use]
+mod macros;
 
+mod chunks;
+mod cursor;
+mod dump;
+mod edit;
+mod fallible;
+mod invariants;
 mod iter;
+mod map;
 mod node;
-
+#[cfg(feature = "rayon")]
+mod par;
+mod rearrange;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "shared")]
+mod shared;
+mod slice;
+#[cfg(feature = "std")]
+mod snapshot;
+mod stats;
+mod windows;
+
+pub use chunks::{Chunks, ChunksMut};
+pub use cursor::{Cursor, CursorMut};
+pub use dump::DebugTree;
+pub use edit::ExtractIf;
+pub use invariants::{InvariantError, InvariantErrorKind};
+#[cfg(feature = "rayon")]
+pub use par::{IntoParIter, ParIter, ParIterMut};
+#[cfg(feature = "shared")]
+pub use shared::SharedMagicList;
+pub use slice::MagicSlice;
+pub use stats::Stats;
+pub use windows::{ChunksExact, RChunks, Windows};
+
+/// A list with O(log n) indexing, insertion, removal, splitting and
+/// concatenation, stored as a B+ tree with branching factor `B`.
+///
+/// A `MagicList` owns all of its nodes and has no interior mutability or
+/// shared ownership, so like `Vec<T, A>` it is `Send` when `T` and `A` are
+/// `Send`, and `Sync` when they are `Sync`. Iterators, cursors and slices
+/// borrow it like `&` and `&mut` references do. This is part of the API and
+/// is checked by the tests. For readers that must not block on a writer, see
+/// `SharedMagicList` behind the `shared` feature.
 #[derive(Clone)]
-pub struct MagicList<T> {
-    root: Node<T>,
+pub struct MagicList<T, const B: usize = 12, A: Allocator = Global> {
+    root: Node<T, B, A>,
 }
 
-impl<T: Debug> Debug for MagicList<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<T: Debug, const B: usize, A: Allocator + Clone> Debug for MagicList<T, B, A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_list().entries(self.iter()).finish()
     }
 }
 
-impl<T> Default for MagicList<T> {
+impl<T, const B: usize, A: Allocator + Clone + Default> Default for MagicList<T, B, A> {
     fn default() -> Self {
-        Self {
-            root: Node::Leaf(vec![]),
-        }
+        Self::new_in(A::default())
     }
 }
 
 impl<T> MagicList<T> {
-    pub fn extend(&mut self, other: Self) {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Used by `magiclist![x; n]`.
+    #[doc(hidden)]
+    pub fn from_elem(x: T, n: usize) -> Self
+    where
+        T: Clone,
+    {
+        Self::build_in(core::iter::repeat_n(x, n), Global)
+    }
+}
+
+impl<T, const N: usize, const B: usize, A: Allocator + Clone + Default> From<[T; N]>
+    for MagicList<T, B, A>
+{
+    fn from(items: [T; N]) -> Self {
+        Self::build_in(items.into_iter(), A::default())
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone + Default> From<alloc::vec::Vec<T>>
+    for MagicList<T, B, A>
+{
+    fn from(items: alloc::vec::Vec<T>) -> Self {
+        Self::build_in(items.into_iter(), A::default())
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            root: Node::new_in(alloc),
+        }
+    }
+
+    /// Builds a list from `items` in O(n), without going through `push`.
+    pub(crate) fn build_in(items: impl ExactSizeIterator<Item = T>, alloc: A) -> Self {
+        Self {
+            root: Node::build(items, alloc),
+        }
+    }
+
+    pub fn allocator(&self) -> &A {
+        self.root.allocator()
+    }
+
+    /// Moves all elements of `other` to the end of `self`, leaving `other`
+    /// empty. Takes O(log n) time.
+    pub fn append(&mut self, other: &mut Self) {
+        let root = other.root.take();
+        self.join(Self { root });
+    }
+
+    pub(crate) fn join(&mut self, other: Self) {
         self.root.extend(other.root);
+        self.grow_root();
+    }
+
+    fn grow_root(&mut self) {
         if self.root.is_overfull() {
-            let len = self.root.len();
             let right = self.root.split_off_half();
-            let left = take(&mut self.root);
-            self.root = Node::Tree(Tree {
-                total_len: len,
-                children: vec![left, right],
-            })
+            let left = self.root.take();
+            let mut children =
+                allocator_api2::vec::Vec::with_capacity_in(2, self.allocator().clone());
+            children.push(left);
+            children.push(right);
+            self.root = Node::Tree(Tree::new(children))
         }
     }
 
     pub fn concat(mut self, right: Self) -> Self {
-        self.extend(right);
+        self.join(right);
         self
     }
 
@@ -57,13 +163,15 @@ impl<T> MagicList<T> {
     }
 
     pub fn push(&mut self, x: T) {
-        self.extend(MagicList {
-            root: Node::Leaf(vec![x]),
+        let mut leaf = allocator_api2::vec::Vec::with_capacity_in(1, self.allocator().clone());
+        leaf.push(x);
+        self.join(Self {
+            root: Node::Leaf(leaf),
         })
     }
 
     fn make_canon(&mut self) {
-        self.root = take(&mut self.root).canon();
+        self.root = self.root.take().canon();
     }
 
     fn canon(self) -> Self {
@@ -76,7 +184,7 @@ impl<T> MagicList<T> {
         let right = self.root.split_off(at).canon();
         self.make_canon();
         self.push(x);
-        self.extend(Self { root: right });
+        self.join(Self { root: right });
     }
 
     pub fn split_off(&mut self, i: usize) -> Self {
@@ -95,7 +203,7 @@ impl<T> MagicList<T> {
     pub fn remove(&mut self, i: usize) -> T {
         let right = self.split_off(i + 1);
         let mid = self.split_off(i);
-        self.extend(right);
+        self.join(right);
         assert!(mid.len() == 1);
         let mid = mid.root.canon();
         match mid {
@@ -110,7 +218,7 @@ impl<T> MagicList<T> {
     }
 }
 
-impl<T> Index<usize> for MagicList<T> {
+impl<T, const B: usize, A: Allocator + Clone> Index<usize> for MagicList<T, B, A> {
     type Output = T;
 
     fn index(&self, index: usize) -> &Self::Output {
@@ -121,13 +229,8 @@ impl<T> Index<usize> for MagicList<T> {
             match node {
                 Node::Leaf(x) => return &x[i],
                 Node::Tree(x) => {
-                    let mut child_i = 0;
-                    let mut j = 0;
-                    while i - j >= x.children[child_i].len() {
-                        j += x.children[child_i].len();
-                        child_i += 1;
-                    }
-                    i -= j;
+                    let (child_i, j) = x.find_child(i);
+                    i = j;
                     node = &x.children[child_i];
                 }
             }
@@ -135,7 +238,7 @@ impl<T> Index<usize> for MagicList<T> {
     }
 }
 
-impl<T> IndexMut<usize> for MagicList<T> {
+impl<T, const B: usize, A: Allocator + Clone> IndexMut<usize> for MagicList<T, B, A> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         assert!(index < self.len(), "out of bounds");
         let mut i = index;
@@ -144,13 +247,8 @@ impl<T> IndexMut<usize> for MagicList<T> {
             match node {
                 Node::Leaf(x) => return &mut x[i],
                 Node::Tree(x) => {
-                    let mut child_i = 0;
-                    let mut j = 0;
-                    while i - j >= x.children[child_i].len() {
-                        j += x.children[child_i].len();
-                        child_i += 1;
-                    }
-                    i -= j;
+                    let (child_i, j) = x.find_child(i);
+                    i = j;
                     node = &mut x.children[child_i];
                 }
             }
@@ -158,29 +256,99 @@ impl<T> IndexMut<usize> for MagicList<T> {
     }
 }
 
-impl<T: Eq> Eq for MagicList<T> {}
+impl<T: Eq, const B: usize, A: Allocator + Clone> Eq for MagicList<T, B, A> {}
 
-impl<T: PartialEq> PartialEq for MagicList<T> {
+impl<T: PartialEq, const B: usize, A: Allocator + Clone> PartialEq for MagicList<T, B, A> {
     fn eq(&self, other: &Self) -> bool {
         self.iter().eq(other.iter())
     }
 }
 
-impl<T: PartialOrd> PartialOrd for MagicList<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl<T: PartialOrd, const B: usize, A: Allocator + Clone> PartialOrd for MagicList<T, B, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         self.iter().partial_cmp(other.iter())
     }
 }
 
-impl<T: Ord> Ord for MagicList<T> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+impl<T: Ord, const B: usize, A: Allocator + Clone> Ord for MagicList<T, B, A> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.iter().cmp(other.iter())
     }
 }
 
+impl<T: PartialEq<U>, U, const B: usize, A: Allocator + Clone> PartialEq<[U]>
+    for MagicList<T, B, A>
+{
+    fn eq(&self, other: &[U]) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<T: PartialEq<U>, U, const B: usize, A: Allocator + Clone> PartialEq<&[U]>
+    for MagicList<T, B, A>
+{
+    fn eq(&self, other: &&[U]) -> bool {
+        *self == **other
+    }
+}
+
+impl<T: PartialEq<U>, U, const B: usize, A: Allocator + Clone> PartialEq<alloc::vec::Vec<U>>
+    for MagicList<T, B, A>
+{
+    fn eq(&self, other: &alloc::vec::Vec<U>) -> bool {
+        *self == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize, const B: usize, A: Allocator + Clone> PartialEq<[U; N]>
+    for MagicList<T, B, A>
+{
+    fn eq(&self, other: &[U; N]) -> bool {
+        *self == other[..]
+    }
+}
+
+impl<T: Hash, const B: usize, A: Allocator + Clone> Hash for MagicList<T, B, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for chunk in self.chunks() {
+            for x in chunk {
+                x.hash(state);
+            }
+        }
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> Add for MagicList<T, B, A> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.concat(rhs)
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> AddAssign for MagicList<T, B, A> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.join(rhs);
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> From<MagicList<T, B, A>> for alloc::vec::Vec<T> {
+    fn from(list: MagicList<T, B, A>) -> Self {
+        list.root.into_elements().collect()
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> From<MagicList<T, B, A>> for VecDeque<T> {
+    fn from(list: MagicList<T, B, A>) -> Self {
+        list.root.into_elements().collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rand::prelude::SliceRandom;
+    use rand::Rng;
     use rand::SeedableRng;
     use rand_chacha::ChaChaRng;
 
@@ -189,7 +357,7 @@ mod tests {
     #[test]
     fn push_back() {
         for size in 0..200 {
-            let mut list = MagicList::default();
+            let mut list = MagicList::new();
             let v: Vec<_> = (0..size).collect();
             for &n in &v {
                 list.push(n);
@@ -202,7 +370,7 @@ mod tests {
     #[test]
     fn push_front() {
         for size in 0..200 {
-            let mut list = MagicList::default();
+            let mut list = MagicList::new();
             let v: Vec<_> = (0..size).collect();
             for &n in &v {
                 list.insert(0, n);
@@ -215,7 +383,7 @@ mod tests {
     #[test]
     fn remove_and_insert() {
         for size in 0..200 {
-            let mut list = MagicList::default();
+            let mut list = MagicList::new();
             let v: Vec<_> = (0..size).collect();
             for &n in &v {
                 list.insert(0, n);
@@ -232,7 +400,7 @@ mod tests {
     #[test]
     fn split_and_merge() {
         for size in 0..200 {
-            let mut list = MagicList::default();
+            let mut list = MagicList::new();
             for n in 0..size {
                 list.push(n);
             }
@@ -251,7 +419,7 @@ mod tests {
             let mut rng = ChaChaRng::seed_from_u64(size);
             v.shuffle(&mut rng);
 
-            let mut list = MagicList::default();
+            let mut list = MagicList::new();
             for x in v {
                 if list.is_empty() {
                     list.push(x);
@@ -276,6 +444,122 @@ mod tests {
         }
     }
 
+    fn random_edits<const B: usize>() {
+        let mut rng = ChaChaRng::seed_from_u64(B as u64);
+        let mut list = MagicList::<_, B>::default();
+        let mut v = vec![];
+        for n in 0..500 {
+            let i = rng.gen_range(0..=v.len());
+            if rng.gen_bool(0.3) && i < v.len() {
+                assert_eq!(list.remove(i), v.remove(i));
+            } else {
+                list.insert(i, n);
+                v.insert(i, n);
+            }
+        }
+        assert!(list.into_iter().eq(v));
+    }
+
+    #[test]
+    fn branching_factors() {
+        random_edits::<2>();
+        random_edits::<3>();
+        random_edits::<64>();
+    }
+
+    #[derive(Default)]
+    struct CountingAlloc {
+        live: std::cell::Cell<usize>,
+    }
+
+    unsafe impl Allocator for &CountingAlloc {
+        fn allocate(
+            &self,
+            layout: std::alloc::Layout,
+        ) -> Result<std::ptr::NonNull<[u8]>, allocator_api2::alloc::AllocError> {
+            self.live.set(self.live.get() + layout.size());
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+            self.live.set(self.live.get() - layout.size());
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn custom_allocator() {
+        let alloc = CountingAlloc::default();
+        let mut list = MagicList::<_, 12, _>::new_in(&alloc);
+        for n in 0..1000 {
+            list.insert(n / 2, n);
+        }
+        let mut right = list.split_off(300);
+        assert!(alloc.live.get() >= 1000 * std::mem::size_of::<i32>());
+        list.append(&mut right);
+        assert_eq!(list.len(), 1000);
+        drop(list);
+        assert_eq!(alloc.live.get(), 0);
+    }
+
+    #[test]
+    fn std_traits() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let mut list: MagicList<_> = (0..10).collect();
+        list.extend(10..20);
+        list.extend(&[20, 21]);
+        list += (22..30).collect();
+        let list = list + (30..40).collect();
+        let v: Vec<i32> = (0..40).collect();
+        assert_eq!(list, v);
+        assert_eq!(list, v[..]);
+        assert_eq!(list, &v[..]);
+        assert_ne!(list, v[1..]);
+        assert_eq!(
+            list.slice(..3).iter().copied().collect::<MagicList<_>>(),
+            [0, 1, 2]
+        );
+
+        let hash = |x: &MagicList<i32>| {
+            let mut hasher = DefaultHasher::new();
+            x.hash(&mut hasher);
+            hasher.finish()
+        };
+        let other_shape = {
+            let mut other = MagicList::new();
+            for &x in v.iter().rev() {
+                other.insert(0, x);
+            }
+            other
+        };
+        assert_eq!(hash(&list), hash(&other_shape));
+        assert_eq!(Vec::from(list.clone()), v);
+        assert!(std::collections::VecDeque::from(list).into_iter().eq(v));
+    }
+
+    #[test]
+    fn send_sync() {
+        fn send<T: Send>() {}
+        fn sync<T: Sync>() {}
+        send::<MagicList<String>>();
+        sync::<MagicList<String>>();
+        send::<iter::Iter<'_, String>>();
+        sync::<iter::Iter<'_, String>>();
+        send::<MagicSlice<'_, String>>();
+        send::<Cursor<'_, String>>();
+        send::<CursorMut<'_, String>>();
+        sync::<CursorMut<'_, String>>();
+        send::<iter::IntoIter<String>>();
+        send::<Chunks<'_, String>>();
+        send::<ChunksMut<'_, String>>();
+        #[cfg(feature = "shared")]
+        {
+            send::<SharedMagicList<String>>();
+            sync::<SharedMagicList<String>>();
+        }
+    }
+
     #[test]
     fn debug() {
         let list: MagicList<_> = (0..10).collect();
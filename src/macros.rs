@@ -0,0 +1,32 @@
+/// Creates a [`MagicList`](crate::MagicList) with the default parameters,
+/// like `vec!`. The elements are bulk-loaded rather than pushed one by one.
+#[macro_export]
+macro_rules! magiclist {
+    () => {
+        <$crate::MagicList<_>>::new()
+    };
+    ($elem:expr; $n:expr) => {
+        <$crate::MagicList<_>>::from_elem($elem, $n)
+    };
+    ($($x:expr),+ $(,)?) => {
+        <$crate::MagicList<_>>::from([$($x),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MagicList;
+
+    #[test]
+    fn forms() {
+        let empty: MagicList<i32> = magiclist![];
+        assert!(empty.is_empty());
+        let list = magiclist![1, 2, 3,];
+        assert_eq!(list, [1, 2, 3]);
+        let long = magiclist![7; 1000];
+        assert_eq!(long.len(), 1000);
+        assert!(long.iter().all(|&x| x == 7));
+        long.check_invariants().unwrap();
+        assert_eq!(magiclist![String::new(); 0].len(), 0);
+    }
+}
@@ -0,0 +1,82 @@
+use core::convert::Infallible;
+
+use allocator_api2::{alloc::Allocator, vec::Vec};
+
+use crate::{
+    node::{Node, Tree},
+    MagicList,
+};
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    /// Applies `f` to every element, keeping the tree shape exactly as it is.
+    /// Nothing is rebalanced, so this is a single O(n) pass.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> MagicList<U, B, A> {
+        match self.try_map(|x| Ok::<_, Infallible>(f(x))) {
+            Ok(list) => list,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Like `map`, but stops at the first error. Elements not yet mapped are
+    /// dropped.
+    pub fn try_map<U, E>(
+        self,
+        mut f: impl FnMut(T) -> Result<U, E>,
+    ) -> Result<MagicList<U, B, A>, E> {
+        Ok(MagicList {
+            root: self.root.try_map(&mut f)?,
+        })
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> Node<T, B, A> {
+    fn try_map<U, E>(self, f: &mut impl FnMut(T) -> Result<U, E>) -> Result<Node<U, B, A>, E> {
+        match self {
+            Node::Leaf(x) => {
+                let mut leaf = Vec::with_capacity_in(x.len(), x.allocator().clone());
+                for y in x {
+                    leaf.push(f(y)?);
+                }
+                Ok(Node::Leaf(leaf))
+            }
+            Node::Tree(x) => {
+                let alloc = x.children.allocator().clone();
+                let mut children = Vec::with_capacity_in(x.children.len(), alloc);
+                for child in x.children {
+                    children.push(child.try_map(f)?);
+                }
+                Ok(Node::Tree(Tree {
+                    total_len: x.total_len,
+                    lens: x.lens,
+                    children,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use super::*;
+
+    #[test]
+    fn keeps_shape() {
+        let mut list = MagicList::<_, 3>::default();
+        for i in 0..500 {
+            list.insert(i / 3, i);
+        }
+        let stats = list.stats();
+        let expected: alloc::vec::Vec<_> = list.iter().map(|x| x.to_string()).collect();
+        let mapped: MagicList<String, 3> = list.clone().map(|x| x.to_string());
+        assert!(mapped.iter().eq(expected.iter()));
+        let mapped_stats = mapped.stats();
+        assert_eq!(mapped_stats.nodes_per_level, stats.nodes_per_level);
+        assert_eq!(mapped_stats.leaf_fill, stats.leaf_fill);
+        mapped.check_invariants().unwrap();
+
+        let err = list.try_map(|x| if x == 250 { Err(x) } else { Ok(x * 2) });
+        assert_eq!(err.unwrap_err(), 250);
+    }
+}
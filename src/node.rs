@@ -1,62 +1,99 @@
-use std::mem::{replace, take};
+use alloc::collections::VecDeque;
+use core::mem::replace;
 
-use crate::B;
+use allocator_api2::{alloc::Allocator, vec::Vec};
 
 #[derive(Debug, Clone)]
-pub(crate) struct Tree<T> {
+pub(crate) struct Tree<T, const B: usize, A: Allocator> {
     pub(crate) total_len: usize,
-    pub(crate) children: Vec<Node<T>>,
+    /// `lens[k] == children[k].len()`, kept next to each other so descent
+    /// only has to scan this array.
+    pub(crate) lens: Vec<usize, A>,
+    pub(crate) children: Vec<Node<T, B, A>, A>,
 }
 
-impl<T> Default for Tree<T> {
-    fn default() -> Self {
+impl<T, const B: usize, A: Allocator + Clone> Tree<T, B, A> {
+    pub(crate) fn new(children: Vec<Node<T, B, A>, A>) -> Self {
+        let mut lens = Vec::with_capacity_in(children.len(), children.allocator().clone());
+        lens.extend(children.iter().map(|x| x.len()));
         Self {
-            total_len: 0,
-            children: vec![],
+            total_len: lens.iter().sum(),
+            lens,
+            children,
         }
     }
-}
 
-impl<T> Tree<T> {
+    pub(crate) fn update_len(&mut self, i: usize) {
+        self.lens[i] = self.children[i].len();
+    }
+
     fn extend(&mut self, other: Self) {
         self.total_len += other.total_len;
+        self.lens.extend(other.lens);
         self.children.extend(other.children);
     }
 
     fn merge_children(&mut self, left_i: usize) {
+        self.lens.remove(left_i + 1);
         let to_merge = self.children.remove(left_i + 1);
         self.children[left_i].extend_equal_level(to_merge);
+        self.update_len(left_i);
     }
 
     fn split_child(&mut self, i: usize) {
         let new_child = self.children[i].split_off_half();
+        self.lens.insert(i + 1, new_child.len());
         self.children.insert(i + 1, new_child);
+        self.update_len(i);
     }
 
     fn rotate_left(&mut self, left_i: usize) {
         let to_move = self.children[left_i + 1].pop_child_left();
         self.children[left_i].extend_equal_level(to_move);
+        self.update_len(left_i);
+        self.update_len(left_i + 1);
     }
 
     fn rotate_right(&mut self, left_i: usize) {
         let to_move = self.children[left_i].pop_child_right();
         self.children[left_i + 1].prepend_equal_level(to_move);
+        self.update_len(left_i);
+        self.update_len(left_i + 1);
+    }
+
+    pub(crate) fn find_child(&self, mut i: usize) -> (usize, usize) {
+        let mut child_i = 0;
+        while child_i + 1 < self.lens.len() && i >= self.lens[child_i] {
+            i -= self.lens[child_i];
+            child_i += 1;
+        }
+        (child_i, i)
     }
 }
 
 #[derive(Debug, Clone)]
-pub(crate) enum Node<T> {
-    Leaf(Vec<T>),
-    Tree(Tree<T>),
+pub(crate) enum Node<T, const B: usize, A: Allocator> {
+    Leaf(Vec<T, A>),
+    Tree(Tree<T, B, A>),
 }
 
-impl<T> Default for Node<T> {
-    fn default() -> Self {
-        Self::Leaf(vec![])
+impl<T, const B: usize, A: Allocator + Clone> Node<T, B, A> {
+    pub(crate) fn new_in(alloc: A) -> Self {
+        Self::Leaf(Vec::new_in(alloc))
+    }
+
+    pub(crate) fn allocator(&self) -> &A {
+        match self {
+            Node::Leaf(x) => x.allocator(),
+            Node::Tree(x) => x.children.allocator(),
+        }
+    }
+
+    pub(crate) fn take(&mut self) -> Self {
+        let empty = Self::new_in(self.allocator().clone());
+        replace(self, empty)
     }
-}
 
-impl<T> Node<T> {
     pub(crate) fn len(&self) -> usize {
         match self {
             Node::Leaf(x) => x.len(),
@@ -64,14 +101,14 @@ impl<T> Node<T> {
         }
     }
 
-    fn children_count(&self) -> usize {
+    pub(crate) fn children_count(&self) -> usize {
         match self {
             Node::Leaf(x) => x.len(),
             Node::Tree(Tree { children, .. }) => children.len(),
         }
     }
 
-    pub(crate) fn split_off_half(&mut self) -> Node<T> {
+    pub(crate) fn split_off_half(&mut self) -> Node<T, B, A> {
         match self {
             Node::Leaf(x) => {
                 let i = x.len() / 2;
@@ -79,14 +116,17 @@ impl<T> Node<T> {
             }
             Node::Tree(Tree {
                 total_len,
+                lens,
                 children,
             }) => {
                 let i = children.len() / 2;
                 let right = children.split_off(i);
-                let right_len = right.iter().map(|x| x.len()).sum::<usize>();
+                let right_lens = lens.split_off(i);
+                let right_len = right_lens.iter().sum::<usize>();
                 *total_len -= right_len;
                 Node::Tree(Tree {
                     total_len: right_len,
+                    lens: right_lens,
                     children: right,
                 })
             }
@@ -98,7 +138,7 @@ impl<T> Node<T> {
             Node::Leaf(x) => Node::Leaf(x),
             Node::Tree(mut x) => {
                 if x.children.is_empty() {
-                    Node::Leaf(vec![])
+                    Node::new_in(x.children.allocator().clone())
                 } else if x.children.len() == 1 {
                     x.children.pop().unwrap().canon()
                 } else {
@@ -109,14 +149,15 @@ impl<T> Node<T> {
     }
 
     pub(crate) fn is_overfull(&self) -> bool {
+        const { assert!(B >= 2, "branching factor must be at least 2") };
         self.children_count() > B * 2
     }
 
-    fn is_underfull(&self) -> bool {
+    pub(crate) fn is_underfull(&self) -> bool {
         self.children_count() < B
     }
 
-    fn extend_equal_level(&mut self, other: Node<T>) {
+    pub(crate) fn extend_equal_level(&mut self, other: Node<T, B, A>) {
         if other.len() == 0 {
             return;
         }
@@ -127,7 +168,7 @@ impl<T> Node<T> {
         }
     }
 
-    fn prepend_equal_level(&mut self, other: Node<T>) {
+    pub(crate) fn prepend_equal_level(&mut self, other: Node<T, B, A>) {
         if other.len() == 0 {
             return;
         }
@@ -137,6 +178,7 @@ impl<T> Node<T> {
             }
             (Node::Tree(a), Node::Tree(b)) => {
                 a.total_len += b.total_len;
+                a.lens.splice(0..0, b.lens);
                 a.children.splice(0..0, b.children);
             }
             _ => unreachable!("must be same type"),
@@ -146,46 +188,60 @@ impl<T> Node<T> {
     fn real_len(&self) -> usize {
         match self {
             Node::Leaf(x) => x.len(),
-            Node::Tree(x) => x.children.iter().map(|x| x.real_len()).sum(),
+            Node::Tree(x) => x
+                .children
+                .iter()
+                .zip(&x.lens)
+                .map(|(x, &len)| {
+                    let real_len = x.real_len();
+                    debug_assert_eq!(real_len, len);
+                    real_len
+                })
+                .sum(),
         }
     }
 
-    pub(crate) fn split_off(&mut self, i: usize) -> Node<T> {
+    pub(crate) fn split_off(&mut self, i: usize) -> Node<T, B, A> {
         match self {
             Node::Leaf(x) => Node::Leaf(x.split_off(i)),
             Node::Tree(tree) => {
                 if i == 0 {
-                    return replace(self, Node::Leaf(vec![]));
+                    return self.take();
                 }
                 if i == tree.total_len {
-                    return Node::Leaf(vec![]);
+                    return Node::new_in(tree.children.allocator().clone());
                 }
                 let orig_len = tree.total_len;
                 let mut child_i = 0;
                 let mut total_before_child_i = 0;
-                let right = loop {
+                let (right, right_lens) = loop {
                     if total_before_child_i == i {
                         let right = tree.children.split_off(child_i);
+                        let right_lens = tree.lens.split_off(child_i);
                         tree.total_len = i;
-                        break right;
+                        break (right, right_lens);
                     }
                     assert!(total_before_child_i < i);
-                    if i < total_before_child_i + tree.children[child_i].len() {
+                    if i < total_before_child_i + tree.lens[child_i] {
                         let mut right = tree.children.split_off(child_i + 1);
-                        tree.total_len = total_before_child_i + tree.children[child_i].len();
+                        let mut right_lens = tree.lens.split_off(child_i + 1);
+                        tree.total_len = total_before_child_i + tree.lens[child_i];
                         if i > total_before_child_i {
                             let extra = tree.children[child_i].split_off(i - total_before_child_i);
                             tree.total_len -= extra.len();
+                            tree.update_len(child_i);
+                            right_lens.insert(0, extra.len());
                             right.insert(0, extra);
                         }
                         assert_eq!(i, tree.total_len);
-                        break right;
+                        break (right, right_lens);
                     }
-                    total_before_child_i += tree.children[child_i].len();
+                    total_before_child_i += tree.lens[child_i];
                     child_i += 1;
                 };
                 let right = Tree {
                     children: right,
+                    lens: right_lens,
                     total_len: orig_len - i,
                 };
                 let mut right = Node::Tree(right);
@@ -201,7 +257,7 @@ impl<T> Node<T> {
         }
     }
 
-    fn depth(&self) -> usize {
+    pub(crate) fn depth(&self) -> usize {
         match self {
             Node::Leaf(_) => 0,
             Node::Tree(x) => {
@@ -216,13 +272,14 @@ impl<T> Node<T> {
 
     fn prepend(&mut self, other: Self) {
         match self.depth().cmp(&other.depth()) {
-            std::cmp::Ordering::Less => {
+            core::cmp::Ordering::Less => {
                 unreachable!("only call from extend")
             }
-            std::cmp::Ordering::Equal => self.prepend_equal_level(other),
-            std::cmp::Ordering::Greater => {
+            core::cmp::Ordering::Equal => self.prepend_equal_level(other),
+            core::cmp::Ordering::Greater => {
                 let Node::Tree(x) = self else { unreachable!() };
                 x.total_len += other.len();
+                x.lens[0] += other.len();
                 x.children.first_mut().unwrap().prepend(other);
                 self.cleanup(0);
             }
@@ -231,86 +288,507 @@ impl<T> Node<T> {
 
     pub(crate) fn extend(&mut self, other: Self) {
         match self.depth().cmp(&other.depth()) {
-            std::cmp::Ordering::Less => {
-                let left = take(self);
+            core::cmp::Ordering::Less => {
+                let left = self.take();
                 let mut right = other;
                 right.prepend(left);
                 right.cleanup(0);
                 *self = right;
             }
-            std::cmp::Ordering::Equal => {
+            core::cmp::Ordering::Equal => {
                 self.extend_equal_level(other);
             }
-            std::cmp::Ordering::Greater => {
+            core::cmp::Ordering::Greater => {
                 let Node::Tree(x) = self else { unreachable!() };
                 x.total_len += other.len();
+                *x.lens.last_mut().unwrap() += other.len();
                 x.children.last_mut().unwrap().extend(other);
                 self.cleanup(self.children_count() - 1);
             }
         }
     }
 
+    /// Brings child `i` back within `B..=2B` children. The child may be out
+    /// of shape by more than one entry, e.g. after `split_off` or `extend`.
     fn cleanup(&mut self, i: usize) {
+        let Node::Tree(x) = self else {
+            return;
+        };
+        let count = x.children[i].children_count();
+        if count < B {
+            if x.children.len() == 1 {
+                return;
+            }
+            // A lone grandchild left behind by `split_off` may itself be out
+            // of shape, so it must be merged into a neighbour and fixed there.
+            let lone = count == 1 && matches!(x.children[i], Node::Tree(_));
+            if count + 1 == B && !lone && i > 0 && x.children[i - 1].children_count() > B {
+                x.rotate_right(i - 1);
+            } else if count + 1 == B
+                && !lone
+                && i + 1 < x.children.len()
+                && x.children[i + 1].children_count() > B
+            {
+                x.rotate_left(i)
+            } else {
+                let (left_i, seam) = if i > 0 {
+                    (i - 1, x.children[i - 1].children_count())
+                } else {
+                    (i, 0)
+                };
+                x.merge_children(left_i);
+                if lone {
+                    x.children[left_i].cleanup(seam);
+                }
+                self.cleanup(left_i);
+            }
+        } else if count > 2 * B {
+            if count == 2 * B + 1 && i > 0 && x.children[i - 1].children_count() < 2 * B {
+                x.rotate_left(i - 1);
+            } else if count == 2 * B + 1
+                && i + 1 < x.children.len()
+                && x.children[i + 1].children_count() < 2 * B
+            {
+                x.rotate_right(i);
+            } else {
+                x.split_child(i);
+                self.cleanup(i + 1);
+                self.cleanup(i);
+            }
+        }
+    }
+
+    pub(crate) fn path_to(&self, mut i: usize) -> (alloc::vec::Vec<usize>, usize) {
+        let mut path = alloc::vec::Vec::new();
+        let mut node = self;
+        while let Node::Tree(tree) = node {
+            let (child_i, j) = tree.find_child(i);
+            path.push(child_i);
+            i = j;
+            node = &tree.children[child_i];
+        }
+        (path, i)
+    }
+
+    pub(crate) fn leaf(&self, path: &[usize]) -> &Vec<T, A> {
+        let mut node = self;
+        for &child_i in path {
+            let Node::Tree(tree) = node else {
+                unreachable!()
+            };
+            node = &tree.children[child_i];
+        }
+        let Node::Leaf(x) = node else { unreachable!() };
+        x
+    }
+
+    pub(crate) fn leaf_mut(&mut self, path: &[usize]) -> &mut Vec<T, A> {
+        let mut node = self;
+        for &child_i in path {
+            let Node::Tree(tree) = node else {
+                unreachable!()
+            };
+            node = &mut tree.children[child_i];
+        }
+        let Node::Leaf(x) = node else { unreachable!() };
+        x
+    }
+
+    /// Moves `path` to the next leaf and returns that leaf, or returns `None`
+    /// if `path` is at the last leaf. Sibling counts are read during a single
+    /// walk along `path`.
+    pub(crate) fn next_leaf(&self, path: &mut [usize]) -> Option<&Vec<T, A>> {
+        let mut fork = None;
+        let mut node = self;
+        for (level, &child_i) in path.iter().enumerate() {
+            let Node::Tree(tree) = node else {
+                unreachable!()
+            };
+            if child_i + 1 < tree.children.len() {
+                fork = Some((level, tree));
+            }
+            node = &tree.children[child_i];
+        }
+        let (level, tree) = fork?;
+        path[level] += 1;
+        let mut node = &tree.children[path[level]];
+        for child_i in &mut path[level + 1..] {
+            let Node::Tree(tree) = node else {
+                unreachable!()
+            };
+            *child_i = 0;
+            node = &tree.children[0];
+        }
+        let Node::Leaf(x) = node else { unreachable!() };
+        Some(x)
+    }
+
+    /// Like `next_leaf`, towards the front.
+    pub(crate) fn prev_leaf(&self, path: &mut [usize]) -> Option<&Vec<T, A>> {
+        let mut fork = None;
+        let mut node = self;
+        for (level, &child_i) in path.iter().enumerate() {
+            let Node::Tree(tree) = node else {
+                unreachable!()
+            };
+            if child_i > 0 {
+                fork = Some((level, tree));
+            }
+            node = &tree.children[child_i];
+        }
+        let (level, tree) = fork?;
+        path[level] -= 1;
+        let mut node = &tree.children[path[level]];
+        for child_i in &mut path[level + 1..] {
+            let Node::Tree(tree) = node else {
+                unreachable!()
+            };
+            *child_i = tree.children.len() - 1;
+            node = tree.children.last().unwrap();
+        }
+        let Node::Leaf(x) = node else { unreachable!() };
+        Some(x)
+    }
+
+    /// Inserts `x` at `offset` in the leaf at `path` and repairs the nodes
+    /// along `path` on the way back up. Afterwards `path` and `offset` point
+    /// at the element that followed the insertion point, and the length of
+    /// its leaf is returned. Only subtrees that were restructured are
+    /// searched again.
+    pub(crate) fn insert_at_path(&mut self, path: &mut [usize], offset: &mut usize, x: T) -> usize {
+        self.edit_at_path(path, offset, true, |leaf, offset| {
+            leaf.insert(*offset, x);
+            *offset += 1;
+        })
+        .1
+    }
+
+    /// Removes the element at `offset` in the leaf at `path`, like
+    /// `insert_at_path`. Afterwards `offset` may equal the returned leaf
+    /// length, meaning the position is at the start of the next leaf.
+    pub(crate) fn remove_at_path(&mut self, path: &mut [usize], offset: &mut usize) -> (T, usize) {
+        self.edit_at_path(path, offset, false, |leaf, offset| leaf.remove(*offset))
+    }
+
+    fn edit_at_path<R>(
+        &mut self,
+        path: &mut [usize],
+        offset: &mut usize,
+        grow: bool,
+        edit: impl FnOnce(&mut Vec<T, A>, &mut usize) -> R,
+    ) -> (R, usize) {
         match self {
-            Node::Leaf(_) => {}
-            Node::Tree(x) => {
-                if x.children[i].is_underfull() {
-                    if i > 0 && x.children[i - 1].children_count() > B {
-                        x.rotate_right(i - 1);
-                    } else if i + 1 < x.children.len() && x.children[i + 1].children_count() > B {
-                        x.rotate_left(i)
-                    } else if i > 0
-                        && x.children[i - 1].children_count() + x.children[i].children_count()
-                            <= 2 * B
-                    {
-                        x.merge_children(i - 1)
-                    } else if i + 1 < x.children.len()
-                        && x.children[i].children_count() + x.children[i + 1].children_count()
-                            <= 2 * B
-                    {
-                        x.merge_children(i)
-                    } else {
-                        assert!(x.children.len() == 1)
-                    }
-                } else if x.children[i].is_overfull() {
-                    if i > 0 && x.children[i - 1].children_count() < 2 * B {
-                        x.rotate_left(i - 1);
-                    } else if i + 1 < x.children.len() && x.children[i + 1].children_count() < 2 * B
-                    {
-                        x.rotate_right(i);
-                    } else {
-                        x.split_child(i);
-                    }
+            Node::Leaf(x) => {
+                let r = edit(x, offset);
+                (r, x.len())
+            }
+            Node::Tree(tree) => {
+                let child_i = path[0];
+                let (r, mut leaf_len) =
+                    tree.children[child_i].edit_at_path(&mut path[1..], offset, grow, edit);
+                if grow {
+                    tree.total_len += 1;
+                    tree.lens[child_i] += 1;
+                } else {
+                    tree.total_len -= 1;
+                    tree.lens[child_i] -= 1;
                 }
+                let child = &tree.children[child_i];
+                if child.is_overfull() || child.is_underfull() {
+                    let i = tree.lens[..child_i].iter().sum::<usize>()
+                        + child.index_of(&path[1..], *offset);
+                    self.cleanup(child_i);
+                    leaf_len = self.locate(i, path, offset);
+                }
+                (r, leaf_len)
             }
         }
     }
 
+    /// Returns the index within this node of position `offset` in the leaf
+    /// at `path`.
+    fn index_of(&self, path: &[usize], offset: usize) -> usize {
+        let mut i = offset;
+        let mut node = self;
+        for &child_i in path {
+            let Node::Tree(tree) = node else {
+                unreachable!()
+            };
+            i += tree.lens[..child_i].iter().sum::<usize>();
+            node = &tree.children[child_i];
+        }
+        i
+    }
+
+    /// Like `path_to`, but writes into an existing `path` of the right depth
+    /// and returns the length of the leaf it ends at.
+    fn locate(&self, mut i: usize, path: &mut [usize], offset: &mut usize) -> usize {
+        let mut node = self;
+        for child_i in path.iter_mut() {
+            let Node::Tree(tree) = node else {
+                unreachable!()
+            };
+            let (c, j) = tree.find_child(i);
+            *child_i = c;
+            i = j;
+            node = &tree.children[c];
+        }
+        *offset = i;
+        node.len()
+    }
+
     fn pop_child_left(&mut self) -> Self {
         match self {
-            Node::Leaf(x) => Node::Leaf(vec![x.remove(0)]),
+            Node::Leaf(x) => {
+                let mut left = Vec::with_capacity_in(1, x.allocator().clone());
+                left.push(x.remove(0));
+                Node::Leaf(left)
+            }
             Node::Tree(x) => {
+                x.lens.remove(0);
                 let left = x.children.remove(0);
                 x.total_len -= left.len();
-                Node::Tree(Tree {
-                    total_len: left.len(),
-                    children: vec![left],
-                })
+                let mut children = Vec::with_capacity_in(1, x.children.allocator().clone());
+                children.push(left);
+                Node::Tree(Tree::new(children))
             }
         }
     }
 
     fn pop_child_right(&mut self) -> Self {
         match self {
-            Node::Leaf(x) => Node::Leaf(vec![x.pop().unwrap()]),
+            Node::Leaf(x) => {
+                let mut right = Vec::with_capacity_in(1, x.allocator().clone());
+                right.push(x.pop().unwrap());
+                Node::Leaf(right)
+            }
             Node::Tree(x) => {
+                x.lens.pop();
                 let right = x.children.pop().unwrap();
                 x.total_len -= right.len();
-                Node::Tree(Tree {
-                    total_len: right.len(),
-                    children: vec![right],
-                })
+                let mut children = Vec::with_capacity_in(1, x.children.allocator().clone());
+                children.push(right);
+                Node::Tree(Tree::new(children))
+            }
+        }
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> Node<T, B, A> {
+    /// Builds a canonical tree from `items` in O(n), packing every node as
+    /// full as the invariants allow.
+    pub(crate) fn build(mut items: impl ExactSizeIterator<Item = T>, alloc: A) -> Self {
+        let n = items.len();
+        match Self::try_build(n, || items.next().ok_or(()), alloc) {
+            Ok(node) => node,
+            Err(()) => unreachable!("ExactSizeIterator yielded too few items"),
+        }
+    }
+
+    /// Like `build`, but takes the `n` elements one by one from a fallible
+    /// source and stops at the first error.
+    pub(crate) fn try_build<E>(
+        n: usize,
+        mut next: impl FnMut() -> Result<T, E>,
+        alloc: A,
+    ) -> Result<Self, E> {
+        let mut leaf = |size: usize| {
+            let mut leaf = Vec::with_capacity_in(size, alloc.clone());
+            for _ in 0..size {
+                leaf.push(next()?);
             }
+            Ok(Node::Leaf(leaf))
+        };
+        if n <= 2 * B {
+            return leaf(n);
+        }
+        let mut level = Vec::new_in(alloc.clone());
+        for size in group_sizes::<B>(n) {
+            level.push(leaf(size)?);
+        }
+        Ok(Self::build_levels(level, alloc))
+    }
+
+    /// Like `build`, for when the number of items is not known up front.
+    /// Leaves are filled to `2B` and only the last two are rebalanced.
+    pub(crate) fn build_from(items: impl Iterator<Item = T>, alloc: A) -> Self {
+        let mut builder = Builder::new(alloc);
+        items.for_each(|x| builder.push(x));
+        builder.finish()
+    }
+
+    /// Stacks inner nodes on top of `level` until a single root remains.
+    fn build_levels(mut level: Vec<Self, A>, alloc: A) -> Self {
+        while level.len() > 2 * B {
+            let sizes = group_sizes::<B>(level.len());
+            let mut next = Vec::with_capacity_in(sizes.len(), alloc.clone());
+            let mut nodes = level.into_iter();
+            for size in sizes {
+                let mut children = Vec::with_capacity_in(size, alloc.clone());
+                children.extend(nodes.by_ref().take(size));
+                next.push(Node::Tree(Tree::new(children)));
+            }
+            level = next;
+        }
+        level.shrink_to_fit();
+        Node::Tree(Tree::new(level))
+    }
+
+    /// Consumes the node, yielding its elements in order from either end.
+    /// Storage is released leaf by leaf as the iterator advances.
+    pub(crate) fn into_elements(self) -> IntoElements<T, B, A> {
+        IntoElements {
+            len: self.len(),
+            nodes: VecDeque::from([self]),
+            front: None,
+            back: None,
         }
     }
 }
+
+/// The state of `Node::build_from` between elements: every leaf in `level`
+/// holds exactly `2B` elements and `leaf` holds the rest. Callers that run
+/// user code between pushes keep it in a drop guard, so a panic loses none of
+/// the elements already pushed.
+pub(crate) struct Builder<T, const B: usize, A: Allocator> {
+    level: Vec<Node<T, B, A>, A>,
+    leaf: Vec<T, A>,
+}
+
+impl<T, const B: usize, A: Allocator + Clone> Builder<T, B, A> {
+    pub(crate) fn new(alloc: A) -> Self {
+        Self {
+            leaf: Vec::with_capacity_in(2 * B, alloc.clone()),
+            level: Vec::new_in(alloc),
+        }
+    }
+
+    pub(crate) fn push(&mut self, x: T) {
+        if self.leaf.len() == 2 * B {
+            let next = Vec::with_capacity_in(2 * B, self.leaf.allocator().clone());
+            let full = replace(&mut self.leaf, next);
+            self.level.push(Node::Leaf(full));
+        }
+        self.leaf.push(x);
+    }
+
+    /// Takes back the element just pushed. Only valid right after `push`,
+    /// which always leaves that element in `leaf`.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        self.leaf.pop()
+    }
+
+    /// Like `pop`, but only borrows the element.
+    pub(crate) fn last_mut(&mut self) -> Option<&mut T> {
+        self.leaf.last_mut()
+    }
+
+    /// Returns the last two elements pushed, earlier one first.
+    pub(crate) fn last_two_mut(&mut self) -> Option<(&mut T, &mut T)> {
+        match &mut self.leaf[..] {
+            [.., prev, last] => Some((prev, last)),
+            [last] => {
+                let Node::Leaf(prev) = self.level.last_mut()? else {
+                    unreachable!()
+                };
+                Some((prev.last_mut().unwrap(), last))
+            }
+            [] => None,
+        }
+    }
+
+    /// Builds the tree from everything pushed so far, leaving the builder
+    /// empty.
+    pub(crate) fn finish(&mut self) -> Node<T, B, A> {
+        let alloc = self.leaf.allocator().clone();
+        let mut level = replace(&mut self.level, Vec::new_in(alloc.clone()));
+        let mut leaf = replace(&mut self.leaf, Vec::new_in(alloc.clone()));
+        if level.is_empty() {
+            leaf.shrink_to_fit();
+            return Node::Leaf(leaf);
+        }
+        if leaf.len() >= B {
+            level.push(Node::Leaf(leaf));
+        } else if !leaf.is_empty() {
+            // The previous leaf is full, so splitting the two evenly leaves
+            // both with at least `B`.
+            let Some(Node::Leaf(prev)) = level.last_mut() else {
+                unreachable!()
+            };
+            prev.extend(leaf);
+            let right = level.last_mut().unwrap().split_off_half();
+            level.push(right);
+        }
+        if level.len() == 1 {
+            return level.pop().unwrap();
+        }
+        Node::build_levels(level, alloc)
+    }
+}
+
+/// Splits `n` entries into the fewest groups of at most `2 * B`, as evenly as
+/// possible. With more than one group, every group gets at least `B`.
+fn group_sizes<const B: usize>(n: usize) -> impl ExactSizeIterator<Item = usize> {
+    let groups = n.div_ceil(2 * B);
+    (0..groups).map(move |k| n / groups + usize::from(k < n % groups))
+}
+
+pub(crate) struct IntoElements<T, const B: usize, A: Allocator> {
+    /// Subtrees not yet entered from either end, in order.
+    nodes: VecDeque<Node<T, B, A>>,
+    front: Option<allocator_api2::vec::IntoIter<T, A>>,
+    back: Option<allocator_api2::vec::IntoIter<T, A>>,
+    len: usize,
+}
+
+impl<T, const B: usize, A: Allocator> Iterator for IntoElements<T, B, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            if let Some(x) = self.front.as_mut().and_then(Iterator::next) {
+                self.len -= 1;
+                return Some(x);
+            }
+            match self.nodes.pop_front() {
+                Some(Node::Leaf(x)) => self.front = Some(x.into_iter()),
+                Some(Node::Tree(x)) => {
+                    for child in x.children.into_iter().rev() {
+                        self.nodes.push_front(child);
+                    }
+                }
+                None => {
+                    let x = self.back.as_mut()?.next()?;
+                    self.len -= 1;
+                    return Some(x);
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const B: usize, A: Allocator> DoubleEndedIterator for IntoElements<T, B, A> {
+    fn next_back(&mut self) -> Option<T> {
+        loop {
+            if let Some(x) = self.back.as_mut().and_then(DoubleEndedIterator::next_back) {
+                self.len -= 1;
+                return Some(x);
+            }
+            match self.nodes.pop_back() {
+                Some(Node::Leaf(x)) => self.back = Some(x.into_iter()),
+                Some(Node::Tree(x)) => self.nodes.extend(x.children),
+                None => {
+                    let x = self.front.as_mut()?.next_back()?;
+                    self.len -= 1;
+                    return Some(x);
+                }
+            }
+        }
+    }
+}
+
+impl<T, const B: usize, A: Allocator> ExactSizeIterator for IntoElements<T, B, A> {}
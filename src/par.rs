@@ -0,0 +1,286 @@
+//! Parallel iteration with rayon. Work is split along `Tree::children`, so
+//! every task owns whole subtrees and never has to search for an index.
+
+use core::slice;
+
+use allocator_api2::alloc::{Allocator, Global};
+use rayon::iter::{
+    plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer},
+    FromParallelIterator, IntoParallelIterator, ParallelIterator,
+};
+
+use crate::{
+    chunks::{Chunks, ChunksMut},
+    node::{Node, Tree},
+    MagicList,
+};
+
+impl<T: Sync, const B: usize, A: Allocator + Clone + Sync> MagicList<T, B, A> {
+    pub fn par_iter(&self) -> ParIter<'_, T, B, A> {
+        ParIter { list: self }
+    }
+}
+
+impl<T: Send, const B: usize, A: Allocator + Clone + Send> MagicList<T, B, A> {
+    pub fn par_iter_mut(&mut self) -> ParIterMut<'_, T, B, A> {
+        ParIterMut { list: self }
+    }
+
+    /// Parallel version of `map`: subtrees are mapped concurrently and the
+    /// tree shape is kept as it is.
+    pub fn par_map<U: Send>(self, f: impl Fn(T) -> U + Sync) -> MagicList<U, B, A> {
+        MagicList {
+            root: self.root.par_map(&f),
+        }
+    }
+}
+
+impl<T: Send, const B: usize, A: Allocator + Clone + Send> Node<T, B, A> {
+    fn par_map<U: Send>(self, f: &(impl Fn(T) -> U + Sync)) -> Node<U, B, A> {
+        match self {
+            Node::Leaf(x) => {
+                let mut leaf =
+                    allocator_api2::vec::Vec::with_capacity_in(x.len(), x.allocator().clone());
+                leaf.extend(x.into_iter().map(f));
+                Node::Leaf(leaf)
+            }
+            Node::Tree(x) => {
+                let alloc = x.children.allocator().clone();
+                let children: Vec<_> = x.children.into_iter().collect();
+                let mapped: Vec<_> = children
+                    .into_par_iter()
+                    .map(|child| child.par_map(f))
+                    .collect();
+                let mut children = allocator_api2::vec::Vec::with_capacity_in(mapped.len(), alloc);
+                children.extend(mapped);
+                Node::Tree(Tree {
+                    total_len: x.total_len,
+                    lens: x.lens,
+                    children,
+                })
+            }
+        }
+    }
+}
+
+/// Parallel iterator over references to the elements of a `MagicList`.
+pub struct ParIter<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a MagicList<T, B, A>,
+}
+
+/// Parallel iterator over mutable references to the elements of a
+/// `MagicList`.
+pub struct ParIterMut<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a mut MagicList<T, B, A>,
+}
+
+/// Owning parallel iterator over the elements of a `MagicList`.
+pub struct IntoParIter<T, const B: usize = 12, A: Allocator = Global> {
+    list: MagicList<T, B, A>,
+}
+
+impl<'a, T: Sync, const B: usize, A: Allocator + Clone + Sync> ParallelIterator
+    for ParIter<'a, T, B, A>
+{
+    type Item = &'a T;
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        let nodes = slice::from_ref(&self.list.root);
+        bridge_unindexed(NodesProducer { nodes }, consumer)
+    }
+}
+
+impl<'a, T: Send, const B: usize, A: Allocator + Clone + Send> ParallelIterator
+    for ParIterMut<'a, T, B, A>
+{
+    type Item = &'a mut T;
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        let nodes = slice::from_mut(&mut self.list.root);
+        bridge_unindexed(NodesMutProducer { nodes }, consumer)
+    }
+}
+
+impl<T: Send, const B: usize, A: Allocator + Clone + Send> ParallelIterator
+    for IntoParIter<T, B, A>
+{
+    type Item = T;
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge_unindexed(
+            NodeProducer {
+                node: self.list.root,
+            },
+            consumer,
+        )
+    }
+}
+
+impl<T: Send, const B: usize, A: Allocator + Clone + Send> IntoParallelIterator
+    for MagicList<T, B, A>
+{
+    type Iter = IntoParIter<T, B, A>;
+
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        IntoParIter { list: self }
+    }
+}
+
+impl<'a, T: Sync, const B: usize, A: Allocator + Clone + Sync> IntoParallelIterator
+    for &'a MagicList<T, B, A>
+{
+    type Iter = ParIter<'a, T, B, A>;
+
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter()
+    }
+}
+
+impl<'a, T: Send, const B: usize, A: Allocator + Clone + Send> IntoParallelIterator
+    for &'a mut MagicList<T, B, A>
+{
+    type Iter = ParIterMut<'a, T, B, A>;
+
+    type Item = &'a mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter_mut()
+    }
+}
+
+/// Each rayon task bulk-loads its share into a subtree; the subtrees are then
+/// joined with `append`.
+impl<T: Send, const B: usize, A: Allocator + Clone + Default + Send> FromParallelIterator<T>
+    for MagicList<T, B, A>
+{
+    fn from_par_iter<I: IntoParallelIterator<Item = T>>(par_iter: I) -> Self {
+        par_iter
+            .into_par_iter()
+            .fold(Vec::new, |mut items, x| {
+                items.push(x);
+                items
+            })
+            .map(|items| Self::build_in(items.into_iter(), A::default()))
+            .reduce(Self::default, |mut left, mut right| {
+                left.append(&mut right);
+                left
+            })
+    }
+}
+
+/// Producer over sibling subtrees of equal depth.
+struct NodesProducer<'a, T, const B: usize, A: Allocator> {
+    nodes: &'a [Node<T, B, A>],
+}
+
+impl<'a, T: Sync, const B: usize, A: Allocator + Clone + Sync> UnindexedProducer
+    for NodesProducer<'a, T, B, A>
+{
+    type Item = &'a T;
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        loop {
+            match self.nodes {
+                [Node::Tree(x)] => self.nodes = &x.children,
+                [_] | [] => return (self, None),
+                nodes => {
+                    let (left, right) = nodes.split_at(nodes.len() / 2);
+                    return (Self { nodes: left }, Some(Self { nodes: right }));
+                }
+            }
+        }
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        folder.consume_iter(Chunks::over(self.nodes).flatten())
+    }
+}
+
+struct NodesMutProducer<'a, T, const B: usize, A: Allocator> {
+    nodes: &'a mut [Node<T, B, A>],
+}
+
+impl<'a, T: Send, const B: usize, A: Allocator + Clone + Send> UnindexedProducer
+    for NodesMutProducer<'a, T, B, A>
+{
+    type Item = &'a mut T;
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        loop {
+            match self.nodes {
+                [Node::Tree(x)] => self.nodes = &mut x.children,
+                [_] | [] => return (self, None),
+                nodes => {
+                    let mid = nodes.len() / 2;
+                    let (left, right) = nodes.split_at_mut(mid);
+                    return (Self { nodes: left }, Some(Self { nodes: right }));
+                }
+            }
+        }
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        folder.consume_iter(ChunksMut::over(self.nodes).flatten())
+    }
+}
+
+/// Producer that owns a subtree, halving it by children to split.
+struct NodeProducer<T, const B: usize, A: Allocator> {
+    node: Node<T, B, A>,
+}
+
+impl<T: Send, const B: usize, A: Allocator + Clone + Send> UnindexedProducer
+    for NodeProducer<T, B, A>
+{
+    type Item = T;
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        loop {
+            match &mut self.node {
+                Node::Tree(x) if x.children.len() == 1 => {
+                    self.node = x.children.pop().unwrap();
+                }
+                Node::Tree(x) if x.children.len() > 1 => {
+                    let right = self.node.split_off_half();
+                    return (self, Some(Self { node: right }));
+                }
+                _ => return (self, None),
+            }
+        }
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        folder.consume_iter(self.node.into_elements())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_sequential() {
+        for n in [0, 1, 7, 1000, 20000] {
+            let mut list: MagicList<u64, 3> = (0..n).collect();
+            assert_eq!(list.par_iter().sum::<u64>(), (0..n).sum::<u64>());
+            list.par_iter_mut().for_each(|x| *x *= 2);
+            (&mut list).into_par_iter().for_each(|x| *x += 1);
+            list.par_iter_mut().for_each(|x| *x -= 1);
+            assert!(list.iter().copied().eq((0..n).map(|x| x * 2)));
+            let v: Vec<u64> = list.into_par_iter().collect();
+            assert!(v.into_iter().eq((0..n).map(|x| x * 2)));
+
+            let list: MagicList<u64, 3> = (0..n).into_par_iter().collect();
+            assert!(list.iter().copied().eq(0..n));
+            list.check_invariants().unwrap();
+
+            let mapped = list.clone().par_map(|x| x as f64 / 2.0);
+            assert!(mapped.iter().copied().eq((0..n).map(|x| x as f64 / 2.0)));
+            assert_eq!(mapped.stats().nodes_per_level, list.stats().nodes_per_level);
+        }
+    }
+}
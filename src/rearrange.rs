@@ -0,0 +1,185 @@
+use alloc::vec::Vec;
+use core::{
+    mem::{replace, swap},
+    ops::RangeBounds,
+};
+
+use allocator_api2::alloc::Allocator;
+
+use crate::{slice::to_range, MagicList};
+
+/// Operations built from `split_off` and `join`. Apart from `concat_all`,
+/// each costs O(log n) per cut.
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    /// Moves the first `k` elements to the end, like `slice::rotate_left`.
+    pub fn rotate_left(&mut self, k: usize) {
+        assert!(k <= self.len(), "out of bounds");
+        let right = self.split_off(k);
+        let left = replace(self, right);
+        self.join(left);
+    }
+
+    /// Moves the last `k` elements to the front, like `slice::rotate_right`.
+    pub fn rotate_right(&mut self, k: usize) {
+        assert!(k <= self.len(), "out of bounds");
+        self.rotate_left(self.len() - k);
+    }
+
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len() && j < self.len(), "out of bounds");
+        let (i, j) = (i.min(j), i.max(j));
+        if i == j {
+            return;
+        }
+        let mut right = self.split_off(j);
+        swap(&mut self[i], &mut right[0]);
+        self.join(right);
+    }
+
+    /// Cuts out the elements in `src` and pastes them back so that they start
+    /// at index `dest` of the result. `dest` may be at most
+    /// `len() - src.len()`.
+    pub fn move_range(&mut self, src: impl RangeBounds<usize>, dest: usize) {
+        let (start, end) = to_range(src, self.len());
+        assert!(dest <= self.len() - (end - start), "out of bounds");
+        let right = self.split_off(end);
+        let moved = self.split_off(start);
+        self.join(right);
+        let tail = self.split_off(dest);
+        self.join(moved);
+        self.join(tail);
+    }
+
+    /// Exchanges the positions of two non-overlapping ranges, which may have
+    /// different lengths. The elements between them shift accordingly.
+    pub fn swap_ranges(&mut self, a: impl RangeBounds<usize>, b: impl RangeBounds<usize>) {
+        let a = to_range(a, self.len());
+        let b = to_range(b, self.len());
+        let ((a_start, a_end), (b_start, b_end)) = if a <= b { (a, b) } else { (b, a) };
+        assert!(a_end <= b_start, "ranges overlap");
+        let tail = self.split_off(b_end);
+        let second = self.split_off(b_start);
+        let middle = self.split_off(a_end);
+        let first = self.split_off(a_start);
+        self.join(second);
+        self.join(middle);
+        self.join(first);
+        self.join(tail);
+    }
+
+    /// Splits the list at each of the sorted `indices`, returning
+    /// `indices.len() + 1` parts in order. Takes O(k log n) for k indices.
+    pub fn split_at_many(mut self, indices: &[usize]) -> Vec<Self> {
+        assert!(
+            indices.windows(2).all(|w| w[0] <= w[1]),
+            "indices must be sorted"
+        );
+        assert!(
+            indices.last().is_none_or(|&i| i <= self.len()),
+            "out of bounds"
+        );
+        let mut parts = Vec::with_capacity(indices.len() + 1);
+        for &i in indices.iter().rev() {
+            parts.push(self.split_off(i));
+        }
+        parts.push(self);
+        parts.reverse();
+        parts
+    }
+
+    /// Concatenates `lists` in order. Neighbours are joined pairwise, round
+    /// after round, so most joins are between trees of similar height and
+    /// cost O(1) instead of O(log n).
+    pub fn concat_all(lists: impl IntoIterator<Item = Self>) -> Self
+    where
+        A: Default,
+    {
+        let mut lists: Vec<Self> = lists.into_iter().collect();
+        while lists.len() > 1 {
+            let mut joined = Vec::with_capacity(lists.len().div_ceil(2));
+            let mut pairs = lists.into_iter();
+            while let Some(left) = pairs.next() {
+                joined.push(match pairs.next() {
+                    Some(right) => left.concat(right),
+                    None => left,
+                });
+            }
+            lists = joined;
+        }
+        lists.pop().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+
+    #[test]
+    fn matches_vec() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        let mut list = MagicList::<_, 2>::default();
+        list.extend(0..300);
+        let mut v: Vec<_> = (0..300).collect();
+        for _ in 0..300 {
+            let len = v.len();
+            let i = rng.gen_range(0..len);
+            let j = rng.gen_range(0..len);
+            match rng.gen_range(0..5) {
+                0 => {
+                    list.rotate_left(i);
+                    v.rotate_left(i);
+                }
+                1 => {
+                    list.rotate_right(i);
+                    v.rotate_right(i);
+                }
+                2 => {
+                    list.swap(i, j);
+                    v.swap(i, j);
+                }
+                3 => {
+                    let (i, j) = (i.min(j), i.max(j));
+                    let dest = rng.gen_range(0..=len - (j - i));
+                    list.move_range(i..j, dest);
+                    let moved: Vec<_> = v.drain(i..j).collect();
+                    v.splice(dest..dest, moved);
+                }
+                _ => {
+                    let mut cuts = [i, j, rng.gen_range(0..=len), rng.gen_range(0..=len)];
+                    cuts.sort();
+                    let [a, b, c, d] = cuts;
+                    list.swap_ranges(c..d, a..b);
+                    let mut w = v[..a].to_vec();
+                    w.extend_from_slice(&v[c..d]);
+                    w.extend_from_slice(&v[b..c]);
+                    w.extend_from_slice(&v[a..b]);
+                    w.extend_from_slice(&v[d..]);
+                    v = w;
+                }
+            }
+            assert_eq!(list, v);
+            list.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn split_many_and_concat_all() {
+        let list: MagicList<_, 3> = (0..1000).collect();
+        let cuts = [0, 0, 7, 100, 101, 640, 1000];
+        let parts = list.clone().split_at_many(&cuts);
+        assert_eq!(parts.len(), cuts.len() + 1);
+        let mut start = 0;
+        for (part, end) in parts.iter().zip(cuts.iter().chain([&1000])) {
+            assert!(part.iter().copied().eq(start..*end));
+            part.check_invariants().unwrap();
+            start = *end;
+        }
+        let joined = MagicList::concat_all(parts);
+        assert_eq!(joined, list);
+        joined.check_invariants().unwrap();
+        assert!(MagicList::<i32, 3>::concat_all([]).is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+use alloc::vec::Vec;
+use core::{fmt, marker::PhantomData};
+
+use allocator_api2::alloc::Allocator;
+use serde::{
+    de::{SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::MagicList;
+
+/// Serializes as a plain sequence, exactly like `Vec<T>`.
+impl<T: Serialize, const B: usize, A: Allocator + Clone> Serialize for MagicList<T, B, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for chunk in self.chunks() {
+            for x in chunk {
+                seq.serialize_element(x)?;
+            }
+        }
+        seq.end()
+    }
+}
+
+/// Collects the sequence first and then builds the tree bottom-up in O(n).
+impl<'de, T: Deserialize<'de>, const B: usize, A: Allocator + Clone + Default> Deserialize<'de>
+    for MagicList<T, B, A>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(ListVisitor(PhantomData))
+    }
+}
+
+struct ListVisitor<T, const B: usize, A>(PhantomData<(T, A)>);
+
+impl<'de, T: Deserialize<'de>, const B: usize, A: Allocator + Clone + Default> Visitor<'de>
+    for ListVisitor<T, B, A>
+{
+    type Value = MagicList<T, B, A>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Self::Value, S::Error> {
+        // Don't trust the hint with a huge up-front allocation.
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(x) = seq.next_element()? {
+            items.push(x);
+        }
+        Ok(MagicList::build_in(items.into_iter(), A::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec};
+
+    use super::*;
+
+    #[test]
+    fn same_format_as_vec() {
+        let list: MagicList<_, 2> = (0..100).collect();
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(
+            json,
+            serde_json::to_string(&(0..100).collect::<Vec<_>>()).unwrap()
+        );
+        let back: MagicList<i32, 2> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, list);
+        back.check_invariants().unwrap();
+
+        let words: MagicList<String> = serde_json::from_str(r#"["a", "b"]"#).unwrap();
+        assert!(words.iter().eq(&vec!["a", "b"]));
+        assert!(serde_json::from_str::<MagicList<i32>>("{}").is_err());
+    }
+}
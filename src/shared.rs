@@ -0,0 +1,102 @@
+//! Concurrent reads with a single writer at a time: readers take a snapshot of
+//! the current list and keep it as long as they like, while a writer prepares
+//! the next version on the side and publishes it with one atomic pointer swap.
+//!
+//! Versions don't share nodes, so this is not a persistent list. Preparing a
+//! version from the previous one means copying it, which `clone_and_update`
+//! spells out in its name.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use allocator_api2::alloc::{Allocator, Global};
+use arc_swap::ArcSwap;
+
+use crate::MagicList;
+
+/// A `MagicList` shared between threads, where readers never block.
+///
+/// Loading a snapshot is lock-free and never waits for a writer. Writers are
+/// serialized among themselves.
+pub struct SharedMagicList<T, const B: usize = 12, A: Allocator = Global> {
+    current: ArcSwap<MagicList<T, B, A>>,
+    writer: Mutex<()>,
+}
+
+impl<T, const B: usize, A: Allocator + Clone> SharedMagicList<T, B, A> {
+    pub fn new(list: MagicList<T, B, A>) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(list),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns the latest published version. Later writes don't affect it.
+    pub fn snapshot(&self) -> Arc<MagicList<T, B, A>> {
+        self.current.load_full()
+    }
+
+    /// Replaces the list wholesale. Readers see either the old or the new
+    /// version, never a mix.
+    pub fn publish(&self, list: MagicList<T, B, A>) {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        self.current.store(Arc::new(list));
+    }
+
+    /// Applies `f` to a copy of the latest version and publishes the result.
+    /// Concurrent calls are applied one after another, so none is lost.
+    ///
+    /// Each call clones the whole list, taking O(n) time and memory no matter
+    /// how small the edit. Batch edits into one call, or build the new list
+    /// elsewhere and `publish` it.
+    pub fn clone_and_update<R>(&self, f: impl FnOnce(&mut MagicList<T, B, A>) -> R) -> R
+    where
+        T: Clone,
+    {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut next = MagicList::clone(&self.current.load());
+        let result = f(&mut next);
+        self.current.store(Arc::new(next));
+        result
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone + Default> Default for SharedMagicList<T, B, A> {
+    fn default() -> Self {
+        Self::new(MagicList::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn readers_see_whole_versions() {
+        let shared = SharedMagicList::<u32>::default();
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let mut last_len = 0;
+                    while last_len < 200 {
+                        let list = shared.snapshot();
+                        assert!(list.len() >= last_len);
+                        assert!(list.iter().copied().eq(0..list.len() as u32));
+                        last_len = list.len();
+                    }
+                });
+            }
+            for _ in 0..2 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        shared.clone_and_update(|list| list.push(list.len() as u32));
+                    }
+                });
+            }
+        });
+        assert_eq!(shared.snapshot().len(), 200);
+        shared.publish(MagicList::new());
+        assert!(shared.snapshot().is_empty());
+    }
+}
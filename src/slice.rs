@@ -0,0 +1,195 @@
+use core::{
+    fmt::Debug,
+    ops::{Bound, Index, RangeBounds},
+};
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::{iter::Iter, MagicList};
+
+pub(crate) fn to_range(range: impl RangeBounds<usize>, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.checked_add(1).expect("out of bounds"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.checked_add(1).expect("out of bounds"),
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end && end <= len, "out of bounds");
+    (start, end)
+}
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> MagicSlice<'_, T, B, A> {
+        let (start, end) = to_range(range, self.len());
+        MagicSlice {
+            list: self,
+            start,
+            end,
+        }
+    }
+}
+
+/// A read-only view of a range of a `MagicList`.
+pub struct MagicSlice<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a MagicList<T, B, A>,
+    start: usize,
+    end: usize,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Clone for MagicSlice<'a, T, B, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Copy for MagicSlice<'a, T, B, A> {}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> MagicSlice<'a, T, B, A> {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<&'a T> {
+        (i < self.len()).then(|| &self.list[self.start + i])
+    }
+
+    pub fn first(&self) -> Option<&'a T> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&'a T> {
+        self.len().checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    pub fn iter(&self) -> Iter<'a, T, B, A> {
+        Iter {
+            list: self.list,
+            i: self.start,
+            j: self.end,
+        }
+    }
+
+    /// Borrows the elements as one contiguous slice if they all live in the
+    /// same leaf, or returns `None` if they span a leaf boundary.
+    pub fn as_slice(&self) -> Option<&'a [T]> {
+        if self.is_empty() {
+            return Some(&[]);
+        }
+        let (leaf, offset) = self.list.chunk_at(self.start);
+        leaf.get(offset..offset + self.len())
+    }
+
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> MagicSlice<'a, T, B, A> {
+        let (start, end) = to_range(range, self.len());
+        MagicSlice {
+            list: self.list,
+            start: self.start + start,
+            end: self.start + end,
+        }
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Index<usize> for MagicSlice<'a, T, B, A> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < self.len(), "out of bounds");
+        &self.list[self.start + index]
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> IntoIterator for MagicSlice<'a, T, B, A> {
+    type Item = &'a T;
+
+    type IntoIter = Iter<'a, T, B, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Debug, const B: usize, A: Allocator + Clone> Debug for MagicSlice<'a, T, B, A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, 'b, T: PartialEq, const B: usize, A: Allocator + Clone> PartialEq<MagicSlice<'b, T, B, A>>
+    for MagicSlice<'a, T, B, A>
+{
+    fn eq(&self, other: &MagicSlice<'b, T, B, A>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, T: Eq, const B: usize, A: Allocator + Clone> Eq for MagicSlice<'a, T, B, A> {}
+
+impl<'a, T: PartialEq, const B: usize, A: Allocator + Clone> PartialEq<MagicList<T, B, A>>
+    for MagicSlice<'a, T, B, A>
+{
+    fn eq(&self, other: &MagicList<T, B, A>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, T: PartialEq, const B: usize, A: Allocator + Clone> PartialEq<MagicSlice<'a, T, B, A>>
+    for MagicList<T, B, A>
+{
+    fn eq(&self, other: &MagicSlice<'a, T, B, A>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, T: PartialEq<U>, U, const B: usize, A: Allocator + Clone> PartialEq<[U]>
+    for MagicSlice<'a, T, B, A>
+{
+    fn eq(&self, other: &[U]) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<'a, T: PartialEq<U>, U, const B: usize, A: Allocator + Clone> PartialEq<&[U]>
+    for MagicSlice<'a, T, B, A>
+{
+    fn eq(&self, other: &&[U]) -> bool {
+        *self == **other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        let list: MagicList<_> = (0..100).collect();
+        let slice = list.slice(10..90);
+        assert_eq!(slice.len(), 80);
+        assert_eq!(slice[0], 10);
+        assert_eq!(slice.first(), Some(&10));
+        assert_eq!(slice.last(), Some(&89));
+        assert_eq!(slice.get(80), None);
+        let inner = slice.slice(5..=9);
+        assert!(inner.iter().copied().eq(15..20));
+        assert_eq!(inner, list.slice(15..20));
+        assert_eq!(format!("{inner:?}"), "[15, 16, 17, 18, 19]");
+        assert!(slice.slice(80..).is_empty());
+        assert_eq!(slice.slice(80..).last(), None);
+        assert_eq!(list.slice(..), list);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn inclusive_end_at_max() {
+        let list: MagicList<i32> = MagicList::default();
+        list.slice(0..=usize::MAX);
+    }
+}
@@ -0,0 +1,219 @@
+//! A compact binary snapshot format.
+//!
+//! Layout, all integers little-endian:
+//!
+//! - magic `b"MGLS"`, then a `u32` format version;
+//! - the total number of elements as a `u64`;
+//! - chunks, one per leaf: a `u32` element count followed by that many
+//!   elements, each a `u32` byte length and the bytes from `encode_elem`;
+//! - a `u64` FNV-1a checksum of everything before it.
+
+use std::io::{self, Read, Write};
+
+use allocator_api2::alloc::Allocator;
+
+use crate::{node::Node, MagicList};
+
+const MAGIC: [u8; 4] = *b"MGLS";
+const VERSION: u32 = 1;
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    /// Writes a snapshot of the list. `encode_elem` appends the encoding of
+    /// one element to the buffer it is given.
+    ///
+    /// Each chunk is written with a single `write_all`; wrap unbuffered
+    /// writers in a `BufWriter` anyway if elements are large.
+    pub fn write_to(
+        &self,
+        w: &mut impl Write,
+        mut encode_elem: impl FnMut(&T, &mut Vec<u8>),
+    ) -> io::Result<()> {
+        let mut w = Hashing::new(w);
+        w.write_all(&MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&(self.len() as u64).to_le_bytes())?;
+        let mut buf = vec![];
+        let mut elem = vec![];
+        for chunk in self.chunks() {
+            buf.clear();
+            buf.extend_from_slice(&to_u32(chunk.len())?.to_le_bytes());
+            for x in chunk {
+                elem.clear();
+                encode_elem(x, &mut elem);
+                buf.extend_from_slice(&to_u32(elem.len())?.to_le_bytes());
+                buf.extend_from_slice(&elem);
+            }
+            w.write_all(&buf)?;
+        }
+        let checksum = w.hash;
+        w.inner.write_all(&checksum.to_le_bytes())
+    }
+
+    /// Reads a snapshot written by [`MagicList::write_to`], building the
+    /// leaves directly as elements are decoded.
+    ///
+    /// Fails with `InvalidData` on a bad header, inconsistent chunk counts or
+    /// a checksum mismatch, and with `UnexpectedEof` on truncated input.
+    /// Wrap unbuffered readers in a `BufReader`.
+    pub fn read_from(
+        r: &mut impl Read,
+        mut decode_elem: impl FnMut(&[u8]) -> io::Result<T>,
+    ) -> io::Result<Self>
+    where
+        A: Default,
+    {
+        let mut r = Hashing::new(r);
+        let mut header = [0; 4];
+        r.read_exact(&mut header)?;
+        if header != MAGIC {
+            return Err(invalid("not a MagicList snapshot"));
+        }
+        if r.read_u32()? != VERSION {
+            return Err(invalid("unsupported snapshot version"));
+        }
+        let len = usize::try_from(r.read_u64()?).map_err(|_| invalid("snapshot too large"))?;
+        let mut left_in_chunk = 0;
+        let mut left = len;
+        let mut elem = vec![];
+        let root = Node::try_build(
+            len,
+            || {
+                if left_in_chunk == 0 {
+                    left_in_chunk = r.read_u32()? as usize;
+                    if left_in_chunk == 0 || left_in_chunk > left {
+                        return Err(invalid("bad chunk length"));
+                    }
+                }
+                left_in_chunk -= 1;
+                left -= 1;
+                let elem_len = r.read_u32()? as u64;
+                elem.clear();
+                if (&mut r).take(elem_len).read_to_end(&mut elem)? as u64 != elem_len {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                decode_elem(&elem)
+            },
+            A::default(),
+        )?;
+        let expected = r.hash;
+        let mut checksum = [0; 8];
+        r.inner.read_exact(&mut checksum)?;
+        if u64::from_le_bytes(checksum) != expected {
+            return Err(invalid("snapshot checksum mismatch"));
+        }
+        Ok(Self { root })
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn to_u32(n: usize) -> io::Result<u32> {
+    u32::try_from(n).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "element too large"))
+}
+
+/// Passes bytes through while folding them into an FNV-1a hash.
+struct Hashing<S> {
+    inner: S,
+    hash: u64,
+}
+
+impl<S> Hashing<S> {
+    fn new(inner: S) -> Self {
+        Self {
+            inner,
+            hash: 0xcbf29ce484222325,
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.hash = (self.hash ^ b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+impl<S: Read> Hashing<S> {
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0; 4];
+        self.read_exact(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        self.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+impl<S: Read> Read for Hashing<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<S: Write> Write for Hashing<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(x: &u32, out: &mut Vec<u8>) {
+        out.extend_from_slice(&x.to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<u32> {
+        let bytes = bytes.try_into().map_err(|_| invalid("bad element"))?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    #[test]
+    fn round_trip() {
+        for n in [0, 1, 4, 5, 100, 1000] {
+            let list: MagicList<_, 2> = (0..n).collect();
+            let mut bytes = vec![];
+            list.write_to(&mut bytes, encode).unwrap();
+            let back = MagicList::<u32, 3>::read_from(&mut &bytes[..], decode).unwrap();
+            assert!(back.iter().eq(list.iter()));
+            back.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn detects_damage() {
+        let list: MagicList<_> = (0..100).collect();
+        let mut bytes = vec![];
+        list.write_to(&mut bytes, encode).unwrap();
+        for end in 0..bytes.len() {
+            let err = MagicList::<u32>::read_from(&mut &bytes[..end], decode).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+        for i in [0, 4, 20, bytes.len() / 2, bytes.len() - 1] {
+            let mut damaged = bytes.clone();
+            damaged[i] ^= 1;
+            let err = MagicList::<u32>::read_from(&mut &damaged[..], decode).unwrap_err();
+            // A damaged length prefix can also run past the end of the input.
+            assert!(
+                matches!(
+                    err.kind(),
+                    io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+                ),
+                "flipped byte {i}"
+            );
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use alloc::{vec, vec::Vec};
+use core::mem::size_of;
+
+use allocator_api2::alloc::Allocator;
+
+use crate::{node::Node, MagicList};
+
+/// A snapshot of the shape and memory use of a `MagicList`, as returned by
+/// [`MagicList::stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of levels below the root. A list whose root is a leaf has
+    /// depth 0.
+    pub depth: usize,
+    /// Number of nodes on each level, starting with the root.
+    pub nodes_per_level: Vec<usize>,
+    /// `leaf_fill[k]` is the number of leaves holding exactly `k` elements.
+    pub leaf_fill: Vec<usize>,
+    pub len: usize,
+    /// Number of elements the leaves can hold without reallocating.
+    pub capacity: usize,
+    /// Estimated bytes allocated for leaves and inner nodes.
+    pub heap_bytes: usize,
+}
+
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    pub fn stats(&self) -> Stats {
+        let depth = self.root.depth();
+        let mut stats = Stats {
+            depth,
+            nodes_per_level: vec![0; depth + 1],
+            leaf_fill: vec![],
+            len: self.len(),
+            capacity: 0,
+            heap_bytes: 0,
+        };
+        self.root.collect_stats(0, &mut stats);
+        stats
+    }
+
+    /// Rebuilds the list with every leaf packed as full as the invariants
+    /// allow and no spare capacity, in O(n).
+    pub fn shrink_to_fit(&mut self) {
+        let alloc = self.allocator().clone();
+        let root = self.root.take();
+        self.root = Node::build(root.into_elements(), alloc);
+    }
+}
+
+impl<T, const B: usize, A: Allocator + Clone> Node<T, B, A> {
+    fn collect_stats(&self, level: usize, stats: &mut Stats) {
+        stats.nodes_per_level[level] += 1;
+        match self {
+            Node::Leaf(x) => {
+                if stats.leaf_fill.len() <= x.len() {
+                    stats.leaf_fill.resize(x.len() + 1, 0);
+                }
+                stats.leaf_fill[x.len()] += 1;
+                stats.capacity += x.capacity();
+                stats.heap_bytes += x.capacity() * size_of::<T>();
+            }
+            Node::Tree(x) => {
+                stats.heap_bytes += x.lens.capacity() * size_of::<usize>()
+                    + x.children.capacity() * size_of::<Self>();
+                for child in x.children.iter() {
+                    child.collect_stats(level + 1, stats);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrink_after_removals() {
+        let mut list = MagicList::<_, 4>::default();
+        for i in 0..1000 {
+            list.insert(i / 2, i);
+        }
+        for i in (0..1000).step_by(3) {
+            list.remove(i / 3 * 2);
+        }
+        let expected: Vec<_> = list.iter().copied().collect();
+        let before = list.stats();
+        assert_eq!(before.len, expected.len());
+        assert_eq!(before.nodes_per_level[0], 1);
+        assert_eq!(
+            before.leaf_fill.iter().sum::<usize>(),
+            before.nodes_per_level[before.depth]
+        );
+        assert!(before.capacity >= before.len);
+
+        list.shrink_to_fit();
+        let after = list.stats();
+        assert!(list.iter().eq(expected.iter()));
+        assert_eq!(after.capacity, after.len);
+        assert!(after.heap_bytes < before.heap_bytes);
+        assert!(after.leaf_fill[..4].iter().all(|&k| k == 0));
+        for n in 0..100 {
+            let mut list: MagicList<_, 2> = (0..n).collect();
+            list.shrink_to_fit();
+            assert!(list.iter().copied().eq(0..n));
+            assert_eq!(list.stats().capacity, n);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use core::iter::FusedIterator;
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::{MagicList, MagicSlice};
+
+/// Fixed-size views, like the slice methods of the same names. Each item is a
+/// `MagicSlice`; use `MagicSlice::as_slice` to borrow it directly when it
+/// falls inside one leaf.
+impl<T, const B: usize, A: Allocator + Clone> MagicList<T, B, A> {
+    pub fn chunks_exact(&self, k: usize) -> ChunksExact<'_, T, B, A> {
+        assert!(k != 0, "chunk size must be non-zero");
+        let end = self.len() - self.len() % k;
+        ChunksExact {
+            list: self,
+            start: 0,
+            end,
+            k,
+        }
+    }
+
+    pub fn rchunks(&self, k: usize) -> RChunks<'_, T, B, A> {
+        assert!(k != 0, "chunk size must be non-zero");
+        RChunks {
+            list: self,
+            end: self.len(),
+            k,
+        }
+    }
+
+    pub fn windows(&self, k: usize) -> Windows<'_, T, B, A> {
+        assert!(k != 0, "window size must be non-zero");
+        Windows {
+            list: self,
+            start: 0,
+            k,
+        }
+    }
+}
+
+/// Iterator returned by [`MagicList::chunks_exact`].
+pub struct ChunksExact<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a MagicList<T, B, A>,
+    start: usize,
+    end: usize,
+    k: usize,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> ChunksExact<'a, T, B, A> {
+    /// The last `len() % k` elements, which no chunk covers.
+    pub fn remainder(&self) -> MagicSlice<'a, T, B, A> {
+        self.list.slice(self.end..)
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Iterator for ChunksExact<'a, T, B, A> {
+    type Item = MagicSlice<'a, T, B, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+        let chunk = self.list.slice(self.start..self.start + self.k);
+        self.start += self.k;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.end - self.start) / self.k;
+        (n, Some(n))
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> ExactSizeIterator for ChunksExact<'a, T, B, A> {}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> FusedIterator for ChunksExact<'a, T, B, A> {}
+
+/// Iterator returned by [`MagicList::rchunks`]. Chunks are taken from the
+/// back, so only the last one yielded can be shorter than `k`.
+pub struct RChunks<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a MagicList<T, B, A>,
+    end: usize,
+    k: usize,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Iterator for RChunks<'a, T, B, A> {
+    type Item = MagicSlice<'a, T, B, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end == 0 {
+            return None;
+        }
+        let start = self.end.saturating_sub(self.k);
+        let chunk = self.list.slice(start..self.end);
+        self.end = start;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end.div_ceil(self.k);
+        (n, Some(n))
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> ExactSizeIterator for RChunks<'a, T, B, A> {}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> FusedIterator for RChunks<'a, T, B, A> {}
+
+/// Iterator returned by [`MagicList::windows`].
+pub struct Windows<'a, T, const B: usize = 12, A: Allocator = Global> {
+    list: &'a MagicList<T, B, A>,
+    start: usize,
+    k: usize,
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> Iterator for Windows<'a, T, B, A> {
+    type Item = MagicSlice<'a, T, B, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start + self.k > self.list.len() {
+            return None;
+        }
+        let window = self.list.slice(self.start..self.start + self.k);
+        self.start += 1;
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.list.len() + 1).saturating_sub(self.start + self.k);
+        (n, Some(n))
+    }
+}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> ExactSizeIterator for Windows<'a, T, B, A> {}
+
+impl<'a, T, const B: usize, A: Allocator + Clone> FusedIterator for Windows<'a, T, B, A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_slices() {
+        for n in [0, 1, 5, 50, 333] {
+            let v: Vec<_> = (0..n).collect();
+            let list: MagicList<_, 2> = v.iter().copied().collect();
+            for k in [1, 2, 3, 4, 7, 400] {
+                let chunks = list.chunks_exact(k);
+                assert_eq!(chunks.len(), v.chunks_exact(k).len());
+                assert_eq!(chunks.remainder(), list.slice(n - n % k..));
+                assert!(chunks.eq(v.chunks_exact(k)));
+                assert!(list.rchunks(k).eq(v.rchunks(k)));
+                assert_eq!(list.windows(k).len(), v.windows(k).len());
+                for (window, expected) in list.windows(k).zip(v.windows(k)) {
+                    assert_eq!(window, expected);
+                    if let Some(slice) = window.as_slice() {
+                        assert_eq!(slice, expected);
+                    }
+                }
+            }
+        }
+        // Windows within a leaf borrow it directly; B = 2 leaves hold 2..=4.
+        let list: MagicList<_, 2> = (0..100).collect();
+        let (leaf, _) = list.chunk_at(0);
+        assert_eq!(list.slice(..leaf.len()).as_slice(), Some(leaf));
+        assert_eq!(list.slice(leaf.len() - 1..leaf.len() + 1).as_slice(), None);
+    }
+}